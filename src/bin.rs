@@ -3,21 +3,43 @@
 #[cfg(not(target_family = "wasm"))]
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    use osrs_cli::OsrsOptions;
-    use std::{io, process};
-    use structopt::StructOpt;
+    use osrs_cli::{OsrsError, OsrsOptions};
+    use std::process;
 
-    let options = OsrsOptions::from_args();
-    let exit_code = match options.run(io::stdout()).await {
-        Ok(()) => 0,
+    let args: Vec<String> = std::env::args().collect();
+    let options = match OsrsOptions::from_iter_with_aliases(args.clone()) {
+        Ok(options) => options,
+        // Help and version output come through as errors, but aren't really
+        Err(OsrsError::InvalidCommand(err)) if !err.use_stderr() => err.exit(),
         Err(err) => {
-            eprintln!("{:#}", err);
-            1
+            let machine_output =
+                OsrsOptions::is_machine_output_requested(&args);
+            process::exit(report_error(&err.into(), machine_output));
         }
     };
+    let machine_output = options.is_machine_output();
+    let exit_code = match options.run_stdout().await {
+        Ok(()) => 0,
+        Err(err) => report_error(&err, machine_output),
+    };
     process::exit(exit_code);
 }
 
+/// Print an error to stderr, and get the exit code for it
+#[cfg(not(target_family = "wasm"))]
+fn report_error(error: &anyhow::Error, machine_output: bool) -> i32 {
+    let report = osrs_cli::ErrorReport::from(error);
+    // If the user wants machine-readable output, give them a machine-readable
+    // error too
+    if machine_output {
+        // This serialization can't fail
+        eprintln!("{}", serde_json::to_string(&report).unwrap());
+    } else {
+        eprintln!("{:#}", error);
+    }
+    report.exit_code
+}
+
 #[cfg(target_family = "wasm")]
 fn main() {
     // Delete after https://github.com/rust-lang/cargo/issues/3138
@@ -0,0 +1,247 @@
+//! Drop rate calculations
+
+use crate::{calc::math, error::OsrsError};
+use derive_more::Display;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+use std::str::FromStr;
+
+/// The chance of getting a drop a certain number of times
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct DropChance {
+    /// Probability (0 to 1) of a success on each roll
+    pub probability: f64,
+    /// Number of chances for the drop, e.g. kill count
+    pub iterations: usize,
+    /// Number of rolls on the loot table per iteration
+    pub rolls: f64,
+    /// The number of successes we want
+    pub target: TargetRange,
+    /// Probability (0 to 1) of hitting the target
+    pub chance: f64,
+}
+
+impl DropChance {
+    /// Calculate the chance of hitting the target number of successes, where
+    /// each of `iterations` attempts gets `rolls` rolls with `probability`
+    /// chance of success. Returns an error if the probability isn't in
+    /// `[0, 1]`.
+    pub fn calculate(
+        probability: f64,
+        iterations: usize,
+        rolls: f64,
+        target: TargetRange,
+    ) -> Result<Self, OsrsError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(OsrsError::ArgsError(format!(
+                "Probability must be between 0 and 1, got: {}",
+                probability
+            )));
+        }
+
+        // Do the cumulative distribution function, which is just to sum
+        // up the probability of all the values in the
+        // range. https://en.wikipedia.org/wiki/Binomial_distribution#Cumulative_distribution_function
+        let total_rolls = (iterations as f64 * rolls).floor() as usize;
+        let chance = math::binomial_cdf(
+            probability,
+            total_rolls,
+            target.as_values(total_rolls),
+        );
+
+        Ok(Self {
+            probability,
+            iterations,
+            rolls,
+            target,
+            chance,
+        })
+    }
+}
+
+/// Parse a probability string, which can be an integer, decimal, fraction, or
+/// percentage. Also enforces that the probability is in [0, 1].
+pub fn parse_probability(s: &str) -> anyhow::Result<f64> {
+    lazy_static! {
+        // regex to match an integer, decimal, fraction, or percentage
+        // see test cases for positive+negative examples
+        static ref RE: Regex =
+            Regex::new(r"^\s*(?P<num>[\d.]+)\s*(?:(/\s*(?P<denom>[\d.]+)\s*)|(?P<pct>%))?\s*$")
+                .unwrap();
+    }
+    let prob: f64 = match RE.captures(s) {
+        None => {
+            return Err(OsrsError::ArgsError(format!(
+            "Invalid probability: {}; Try a decimal, percentage, or fraction.",
+            s,
+        ))
+            .into())
+        }
+        Some(caps) => {
+            // numerator is a required group so unwrap is safe
+            let numerator: f64 = caps.name("num").unwrap().as_str().parse()?;
+
+            let denom_opt = caps.name("denom");
+            let is_pct = caps.name("pct").is_some();
+
+            match (denom_opt, is_pct) {
+                (Some(denom_match), false) => {
+                    let denominator: f64 = denom_match.as_str().parse()?;
+                    numerator / denominator
+                }
+                (None, false) => numerator,
+                (None, true) => numerator / 100.0,
+                // This case shouldn't be possible because the regex makes them
+                // mutually exclusive
+                (Some(_), true) => {
+                    panic!("Received both fraction and percentage!")
+                }
+            }
+        }
+    };
+
+    // Make sure the value is in range
+    if (0.0..=1.0).contains(&prob) {
+        Ok(prob)
+    } else {
+        Err(OsrsError::ArgsError(format!(
+            "Probability must be in range [0, 1], but got: {}",
+            prob,
+        ))
+        .into())
+    }
+}
+
+/// A target number of successes: an exact value, or a range bounded on one
+/// side. Parse from strings like `1`, `3-` (3 or fewer) or `1+` (1 or more).
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+pub enum TargetRange {
+    #[display(fmt = "{}", _0)]
+    Eq(usize),
+    #[display(fmt = "≤{}", _0)]
+    Lte(usize),
+    #[display(fmt = "≥{}", _0)]
+    Gte(usize),
+}
+
+impl TargetRange {
+    /// Convert to an iterator of values. This should cover all values in the
+    /// target range, as a sub-set of `[0, iterations]`.
+    pub fn as_values(&self, iterations: usize) -> impl Iterator<Item = usize> {
+        match self {
+            Self::Eq(k) => *k..=*k,
+            Self::Lte(k) => 0..=*k,
+            Self::Gte(k) => *k..=iterations,
+        }
+    }
+}
+
+impl Serialize for TargetRange {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse an input string for the target number of success into a numerical
+/// range. This looks for exact values, a range <= to a given value, or >= to
+/// a given value.
+impl FromStr for TargetRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^(\d+)([-+]?)$").unwrap();
+        }
+        match RE.captures(s) {
+            // no buen
+            None => Err(OsrsError::ArgsError(format!(
+                "Invalid target range: {}",
+                s
+            ))
+            .into()),
+            // buen
+            Some(caps) => {
+                // Both these groups match always so if the regex matches, they
+                // should both have values
+                let k: usize = caps.get(1).unwrap().as_str().parse()?;
+                let sign = caps.get(2).unwrap().as_str();
+                let result = match sign {
+                    "" => Self::Eq(k),
+                    "-" => Self::Lte(k),
+                    "+" => Self::Gte(k),
+                    // Regex shouldn't let any other values through
+                    other => {
+                        panic!("Regex allowed invalid sign char: {}", other)
+                    }
+                };
+                Ok(result)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_parse_probability() {
+        // basic success cases
+        assert_approx_eq!(parse_probability("0").unwrap(), 0.0);
+        assert_approx_eq!(parse_probability("1").unwrap(), 1.0);
+        assert_approx_eq!(parse_probability("0.5").unwrap(), 0.5);
+        assert_approx_eq!(parse_probability("50%").unwrap(), 0.5);
+        assert_approx_eq!(parse_probability("1/2").unwrap(), 0.5);
+        assert_approx_eq!(parse_probability("0.5%").unwrap(), 0.005);
+
+        // whitespace is ignored
+        assert_approx_eq!(parse_probability(" 0.5 ").unwrap(), 0.5);
+        assert_approx_eq!(parse_probability(" 1 / 2 ").unwrap(), 0.5);
+        assert_approx_eq!(parse_probability(" 50 % ").unwrap(), 0.5);
+
+        // fractions w/ decimals
+        assert_approx_eq!(parse_probability("1 / 25.6").unwrap(), 0.0390625);
+    }
+
+    #[test]
+    fn test_parse_probability_errors() {
+        // basic success cases
+        assert!(parse_probability("5").is_err());
+        assert!(parse_probability("%5").is_err());
+        assert!(parse_probability("-5").is_err());
+        assert!(parse_probability("1 / 2 / 3").is_err());
+        assert!(parse_probability("0.5.5").is_err());
+        assert!(parse_probability("5% / 5").is_err());
+        assert!(parse_probability("1/2%").is_err());
+    }
+
+    #[test]
+    fn test_parse_target_range() {
+        assert_eq!("3".parse::<TargetRange>().unwrap(), TargetRange::Eq(3));
+        assert_eq!("3-".parse::<TargetRange>().unwrap(), TargetRange::Lte(3));
+        assert_eq!("1+".parse::<TargetRange>().unwrap(), TargetRange::Gte(1));
+        assert!("+1".parse::<TargetRange>().is_err());
+        assert!("-1".parse::<TargetRange>().is_err());
+    }
+
+    #[test]
+    fn test_drop_chance() {
+        let drop =
+            DropChance::calculate(0.5, 2, 1.0, TargetRange::Gte(1)).unwrap();
+        assert_approx_eq!(drop.chance, 0.75);
+
+        // Multiple rolls per kill
+        let drop =
+            DropChance::calculate(0.5, 1, 2.0, TargetRange::Eq(2)).unwrap();
+        assert_approx_eq!(drop.chance, 0.25);
+
+        assert!(
+            DropChance::calculate(1.5, 1, 1.0, TargetRange::Gte(1)).is_err()
+        );
+    }
+}
@@ -1,3 +1,6 @@
+//! Probability math that the calculators are built on. Use these directly for
+//! calculations the calculators don't cover.
+
 use std::collections::HashSet;
 
 /// Calculate numerical combination `nCk` (n choose k).
@@ -19,6 +22,10 @@ pub fn combination(n: usize, k: usize) -> usize {
 /// Calculate the binomial distribution. This calculates the odds of getting
 /// **exactly** `k` successes in `n` trials, where each trial has `p`
 /// probability of success.
+///
+/// # Panics
+///
+/// Panics if `p` isn't in `[0, 1]`, or if `k` is greater than `n`.
 // https://en.wikipedia.org/wiki/Binomial_distribution
 pub fn binomial(p: f64, n: usize, k: usize) -> f64 {
     // Validate inputs
@@ -40,6 +47,18 @@ pub fn binomial(p: f64, n: usize, k: usize) -> f64 {
 /// a single one. Calculates the odds of getting `k_x` successes in `n` trials,
 /// where `k_x` is any value in `k_values` and each trial has `p` probability of
 /// success.
+///
+/// ```
+/// use osrs_cli::calc::math::binomial_cdf;
+///
+/// // Chance of getting a 1/50 drop at least once in 50 kills
+/// let chance = binomial_cdf(1.0 / 50.0, 50, 1..=50);
+/// assert!((chance - 0.636).abs() < 0.001);
+/// ```
+///
+/// # Panics
+///
+/// Panics under the same conditions as [binomial], for any value of `k`.
 pub fn binomial_cdf(
     p: f64,
     n: usize,
@@ -0,0 +1,7 @@
+//! Calculators for common in-game questions. These are pure functions, so
+//! they don't need any config or network access.
+
+pub mod drop;
+pub mod math;
+pub mod stew;
+pub mod xp;
@@ -0,0 +1,179 @@
+//! Spicy stew boost calculations
+
+use crate::{calc::math, error::OsrsError};
+
+/// Maximum number of doses per stew
+pub const MAX_DOSES: usize = 3;
+
+/// Highest possible boost
+pub const MAX_BOOST: usize = 5;
+
+/// For each dose count, the odds of getting AT LEAST that boost (+0 through +5)
+/// https://oldschool.runescape.wiki/w/Spicy_stew#Probability
+const CUMULATIVE_PROBS: [[f64; MAX_BOOST + 1]; MAX_DOSES] = [
+    [0.750, 0.250, 0.000, 0.000, 0.000, 0.000], // 1 dose
+    [0.625, 0.375, 0.250, 0.125, 0.000, 0.000], // 2 doses
+    [0.583, 0.417, 0.333, 0.250, 0.167, 0.083], // 3 doses
+];
+
+/// A number of levels to boost by, from 1 to [MAX_BOOST]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Boost(pub usize);
+
+impl Boost {
+    /// Create a boost value, checking that it's in the supported range
+    pub fn new(boost: usize) -> Result<Self, OsrsError> {
+        if (1..=MAX_BOOST).contains(&boost) {
+            Ok(Self(boost))
+        } else {
+            Err(OsrsError::ArgsError(format!(
+                "Boost must be between 1 and {} levels, got: {}",
+                MAX_BOOST, boost
+            )))
+        }
+    }
+
+    /// Convert an array index to a doses value (from 0-indexed to 1-indexed)
+    fn from_index(index: usize) -> Self {
+        Self(index + 1)
+    }
+
+    /// Convert this value to an array index (from 1-indexed to 0-indexed)
+    fn to_index(self) -> usize {
+        self.0 - 1
+    }
+}
+
+/// A number of spice doses
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Doses(pub usize);
+
+impl Doses {
+    /// Convert an array index to a doses value (from 0-indexed to 1-indexed)
+    fn from_index(index: usize) -> Self {
+        Self(index + 1)
+    }
+
+    /// Convert this value to an array index (from 1-indexed to 0-indexed)
+    fn to_index(self) -> usize {
+        self.0 - 1
+    }
+}
+
+/// Odds of hitting each boost level, for each number of doses per stew, given
+/// a fixed total number of doses
+#[derive(Clone, Debug)]
+pub struct Probabilities {
+    /// A table of doses:boost probabilities. Lookup by doses **then** boost.
+    probabilities: [[f64; MAX_BOOST]; MAX_DOSES],
+}
+
+impl Probabilities {
+    /// Build a table of probabilities for each dose/boost level, for the
+    /// total number of doses available
+    pub fn calculate(total_doses: Doses) -> Self {
+        let mut probabilities = [
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+        ];
+
+        // Use newtypes to make sure we don't mix up boosts/doses, and also
+        // to differentiate 0-indexed (usize) from 1-indexed (newtypes).
+        #[allow(clippy::needless_range_loop)]
+        for doses_per_stew in 1..=MAX_DOSES {
+            let doses_per_stew = Doses(doses_per_stew);
+            for boost in 1..=MAX_BOOST {
+                let boost = Boost(boost);
+                probabilities[doses_per_stew.to_index()][boost.to_index()] =
+                    Self::prob_for_stews(boost, total_doses, doses_per_stew)
+            }
+        }
+
+        Self { probabilities }
+    }
+
+    /// Calculate the probability of hitting AT LEAST the specified boost level
+    /// AT LEAST once in `n` trials, where `n` is the total number of doses we
+    /// have available over the number of doses per stew.
+    fn prob_for_stews(
+        boost: Boost,
+        total_doses: Doses,
+        doses_per_stew: Doses,
+    ) -> f64 {
+        let total_stews = total_doses.0 / doses_per_stew.0; // rounded down
+
+        // +1 on the boost index because CUMULATIVE_PROBS has an extra column
+        // for boost=0
+        let prob_per_stew =
+            CUMULATIVE_PROBS[doses_per_stew.to_index()][boost.to_index() + 1];
+
+        math::binomial_cdf(prob_per_stew, total_stews, &mut (1..=total_stews))
+    }
+
+    /// Based on a given probabilities table, calculate the optimal number of
+    /// doses to use per stew to get the highest probability of hitting the
+    /// requested boost level.
+    pub fn optimal_doses(&self, target_boost: Boost) -> Doses {
+        let mut best: (Doses, f64) = (Doses(1), 0.0);
+        for (dose_idx, probabilities) in self.probabilities.iter().enumerate() {
+            let prob = probabilities[target_boost.to_index()];
+            if prob >= best.1 {
+                best = (Doses::from_index(dose_idx), prob);
+            }
+        }
+        best.0
+    }
+
+    /// Get an iterator for this table. Outer iterator is table rows
+    /// (probabilities for a dose count), inner iterators are (boost,
+    /// probability) pairs.
+    pub fn doses_iter(
+        &self,
+    ) -> impl Iterator<Item = (Doses, Vec<(Boost, f64)>)> + '_ {
+        self.probabilities.iter().enumerate().map(
+            |(doses_idx, row_probabilities)| {
+                (
+                    Doses::from_index(doses_idx),
+                    row_probabilities
+                        .iter()
+                        .enumerate()
+                        .map(|(boost_idx, prob)| {
+                            (Boost::from_index(boost_idx), *prob)
+                        })
+                        .collect(),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_boost_range() {
+        assert!(Boost::new(0).is_err());
+        assert_eq!(Boost::new(1).unwrap(), Boost(1));
+        assert_eq!(Boost::new(MAX_BOOST).unwrap(), Boost(MAX_BOOST));
+        assert!(Boost::new(MAX_BOOST + 1).is_err());
+    }
+
+    #[test]
+    fn test_probabilities() {
+        let probabilities = Probabilities::calculate(Doses(3));
+        let rows: Vec<_> = probabilities.doses_iter().collect();
+        assert_eq!(rows.len(), MAX_DOSES);
+
+        // One stew with all 3 doses
+        let (doses, boosts) = &rows[2];
+        assert_eq!(*doses, Doses(3));
+        assert_approx_eq!(boosts[0].1, 0.417);
+        assert_approx_eq!(boosts[4].1, 0.083);
+
+        // +5 is only possible with 3 doses in one stew
+        assert_eq!(probabilities.optimal_doses(Boost(5)), Doses(3));
+    }
+}
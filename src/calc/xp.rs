@@ -0,0 +1,122 @@
+//! XP and level conversions
+
+use crate::error::OsrsError;
+use serde::Serialize;
+
+/// A list of the XP total required for each level. The index is (level-1), so
+/// it starts with level 1. Be careful with index conversions! Goes up to the
+/// the first impossible level (the first one past 200m).
+const LEVEL_TO_XP: &[usize] = &[
+    // Trust me, this is easier than computing it
+    0, 83, 174, 276, 388, 512, 650, 801, 969, 1154, 1358, 1584, 1833, 2107,
+    2411, 2746, 3115, 3523, 3973, 4470, 5018, 5624, 6291, 7028, 7842, 8740,
+    9730, 10824, 12031, 13363, 14833, 16456, 18247, 20224, 22406, 24815, 27473,
+    30408, 33648, 37224, 41171, 45529, 50339, 55649, 61512, 67983, 75127,
+    83014, 91721, 101333, 111945, 123660, 136594, 150872, 166636, 184040,
+    203254, 224466, 247886, 273742, 302288, 333804, 368599, 407015, 449428,
+    496254, 547953, 605032, 668051, 737627, 814445, 899257, 992895, 1096278,
+    1210421, 1336443, 1475581, 1629200, 1798808, 1986068, 2192818, 2421087,
+    2673114, 2951373, 3258594, 3597792, 3972294, 4385776, 4842295, 5346332,
+    5902831, 6517253, 7195629, 7944614, 8771558, 9684577, 10692629, 11805606,
+    13034431, 14391160, 15889109, 17542976, 19368992, 21385073, 23611006,
+    26068632, 28782069, 31777943, 35085654, 38737661, 42769801, 47221641,
+    52136869, 57563718, 63555443, 70170840, 77474828, 85539082, 94442737,
+    104273167, 115126838, 127110260, 140341028, 154948977, 171077457,
+    188884740, 208545572,
+];
+
+/// Convert the given level to an XP total. Returns an error if the given level
+/// is outside the supported range.
+pub fn level_to_xp(level: usize) -> Result<usize, OsrsError> {
+    if 1 <= level && level <= LEVEL_TO_XP.len() {
+        Ok(LEVEL_TO_XP[level - 1])
+    } else {
+        Err(OsrsError::InvalidLevel(level))
+    }
+}
+
+/// Convert the XP total to a level. XP past the last supported level maps to
+/// that level.
+pub fn xp_to_level(xp: usize) -> usize {
+    let index = match LEVEL_TO_XP.binary_search(&xp) {
+        Ok(idx) => idx,
+        Err(idx) => idx - 1,
+    };
+    index + 1
+}
+
+/// The XP difference between two XP totals, with the level for each
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct XpDiff {
+    pub source_xp: usize,
+    pub source_level: usize,
+    pub dest_xp: usize,
+    pub dest_level: usize,
+    /// XP needed to get from source to dest. Negative if dest < source
+    pub xp_diff: isize,
+}
+
+impl XpDiff {
+    /// Calculate the XP needed to get from one XP total to another
+    pub fn new(source_xp: usize, dest_xp: usize) -> Self {
+        Self {
+            source_xp,
+            source_level: xp_to_level(source_xp),
+            dest_xp,
+            dest_level: xp_to_level(dest_xp),
+            // This difference can be negative, so we cast to isize _after_
+            // subtraction. If the diff is negative, the result of
+            // wrapping_sub will be some very large number, but after the cast
+            // it will be correct
+            xp_diff: dest_xp.wrapping_sub(source_xp) as isize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xp_diff() {
+        let diff = XpDiff::new(0, 83);
+        assert_eq!(diff.source_level, 1);
+        assert_eq!(diff.dest_level, 2);
+        assert_eq!(diff.xp_diff, 83);
+
+        // Going backwards gives a negative diff
+        assert_eq!(XpDiff::new(13_034_431, 6_517_253).xp_diff, -6_517_178);
+    }
+
+    #[test]
+    fn test_level_to_xp() {
+        assert_eq!(
+            level_to_xp(0).unwrap_err().to_string(),
+            "Invalid level. Must be between 1 and 127, got: 0"
+        );
+        assert_eq!(level_to_xp(1).unwrap(), 0);
+        assert_eq!(level_to_xp(2).unwrap(), 83);
+        assert_eq!(level_to_xp(92).unwrap(), 6_517_253);
+        assert_eq!(level_to_xp(99).unwrap(), 13_034_431);
+        assert_eq!(level_to_xp(126).unwrap(), 188_884_740);
+        assert_eq!(level_to_xp(127).unwrap(), 208_545_572);
+        assert_eq!(
+            level_to_xp(128).unwrap_err().to_string(),
+            "Invalid level. Must be between 1 and 127, got: 128"
+        );
+    }
+
+    #[test]
+    fn test_xp_to_level() {
+        assert_eq!(xp_to_level(0), 1);
+        assert_eq!(xp_to_level(1), 1);
+        assert_eq!(xp_to_level(82), 1);
+        assert_eq!(xp_to_level(83), 2);
+        assert_eq!(xp_to_level(37223), 39);
+        assert_eq!(xp_to_level(37224), 40);
+        assert_eq!(xp_to_level(6_517_253), 92);
+        assert_eq!(xp_to_level(13_034_431), 99);
+        assert_eq!(xp_to_level(200_000_000), 126);
+        assert_eq!(xp_to_level(999_999_999), 127);
+    }
+}
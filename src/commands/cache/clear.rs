@@ -0,0 +1,27 @@
+use crate::{
+    commands::Command,
+    utils::{cache::PersistentCache, context::CommandContext},
+};
+use async_trait::async_trait;
+use std::io::Write;
+use structopt::StructOpt;
+
+/// Delete all cached API responses. They will be re-fetched when needed.
+#[derive(Debug, StructOpt)]
+pub struct CacheClearCommand {}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for CacheClearCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        let deleted = PersistentCache::clear()?;
+        context
+            .println_fmt(format_args!("Deleted {} cache entries", deleted))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    commands::{
+        cache::{clear::CacheClearCommand, show::CacheShowCommand},
+        Command, CommandType,
+    },
+    utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::io::Write;
+use structopt::StructOpt;
+
+mod clear;
+mod show;
+
+#[derive(Debug, StructOpt)]
+pub enum CacheCommandType {
+    Show(CacheShowCommand),
+    Clear(CacheClearCommand),
+}
+
+impl<O: Write> CommandType<O> for CacheCommandType {
+    fn command(&self) -> &dyn Command<O> {
+        match &self {
+            Self::Show(cmd) => cmd,
+            Self::Clear(cmd) => cmd,
+        }
+    }
+}
+
+/// Inspect and clear cached API responses.
+#[derive(Debug, StructOpt)]
+pub struct CacheCommand {
+    #[structopt(subcommand)]
+    pub cmd: CacheCommandType,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for CacheCommand {
+    async fn execute(&self, context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        self.cmd.command().execute(context).await
+    }
+}
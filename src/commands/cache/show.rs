@@ -0,0 +1,69 @@
+use crate::{
+    commands::Command,
+    utils::{
+        cache::PersistentCache, context::CommandContext, fmt, table::TableExt,
+    },
+};
+use async_trait::async_trait;
+use comfy_table::{CellAlignment, Table};
+use serde::Serialize;
+use std::io::Write;
+use structopt::StructOpt;
+
+/// Show all cached API responses.
+#[derive(Debug, StructOpt)]
+pub struct CacheShowCommand {}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for CacheShowCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        let entries = PersistentCache::list()?;
+
+        if context.format().is_machine() {
+            let rows: Vec<CacheRow> = entries
+                .iter()
+                .map(|(key, entry)| CacheRow {
+                    key,
+                    fetched_at: entry.fetched_at,
+                    size: entry.body.len(),
+                })
+                .collect();
+            context.print_records(&rows)?;
+        } else if entries.is_empty() {
+            context.println("Cache is empty")?;
+        } else {
+            let mut table = Table::new();
+            table.set_aligned_header([
+                ("Key", CellAlignment::Left),
+                ("Age", CellAlignment::Right),
+                ("Size", CellAlignment::Right),
+            ]);
+            for (key, entry) in entries {
+                table.add_row(vec![
+                    key,
+                    fmt::fmt_duration(entry.age()),
+                    format!("{} KB", fmt::fmt_int(&(entry.body.len() / 1024))),
+                ]);
+            }
+            context.print_table(&mut table)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One row of machine-readable cache output
+#[derive(Debug, Serialize)]
+struct CacheRow<'a> {
+    key: &'a str,
+    /// When the response was fetched, in seconds since the Unix epoch
+    fetched_at: u64,
+    /// Size of the response body, in bytes
+    size: usize,
+}
@@ -0,0 +1,182 @@
+use crate::{
+    commands::Command,
+    prices::{self, Item},
+    utils::{
+        context::CommandContext,
+        http::{self, CachePolicy},
+    },
+    OsrsOptions,
+};
+use async_trait::async_trait;
+use std::{collections::BTreeSet, io::Write};
+use structopt::{clap::Shell, StructOpt};
+
+/// Extra bash completion logic, appended to the generated script. If the
+/// `complete-item` command gives us item names, use those. Otherwise fall back
+/// to the generated completions.
+const BASH_ITEM_COMPLETIONS: &str = r#"
+_osrs_with_items() {
+    mapfile -t COMPREPLY < <(osrs complete-item -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)
+    if [[ ${#COMPREPLY[@]} -eq 0 ]]; then
+        _osrs "$@"
+    fi
+}
+
+complete -F _osrs_with_items -o bashdefault -o default osrs
+"#;
+
+/// Extra fish completion logic, appended to the generated script
+const FISH_ITEM_COMPLETIONS: &str = r#"
+complete -c osrs -n "__fish_seen_subcommand_from price ge" -f -a "(osrs complete-item -- (commandline -opc) (commandline -ct))"
+"#;
+
+/// Print a shell completion script. For example, for bash:
+/// `osrs completions bash > /etc/bash_completion.d/osrs`
+#[derive(Debug, StructOpt)]
+pub struct CompletionsCommand {
+    /// The shell to generate completions for
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    shell: Shell,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for CompletionsCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        OsrsOptions::clap().gen_completions_to(
+            "osrs",
+            self.shell,
+            &mut context.output,
+        );
+
+        // Item names are dynamic, so they need some custom logic. We only
+        // support that for some shells.
+        match self.shell {
+            Shell::Bash => context.println(BASH_ITEM_COMPLETIONS)?,
+            Shell::Fish => context.println(FISH_ITEM_COMPLETIONS)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Print completions for an item name, based on cached item data. This is
+/// called by the shell completion scripts, and not meant to be used directly.
+#[derive(Debug, StructOpt)]
+pub struct CompleteItemCommand {
+    /// All words on the command line, up to and including the one being
+    /// completed
+    words: Vec<String>,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for CompleteItemCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        // Completions need to be fast, so never hit the network. If nothing's
+        // cached, then we just don't have any completions.
+        http::set_cache_policy(CachePolicy::Offline);
+        let items = prices::search(context.config(), "")
+            .await
+            .unwrap_or_default();
+
+        for completion in item_completions(&items, &self.words) {
+            context.println(&completion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get completions for the word currently being typed, if it's part of the
+/// query for a price command. `words` should be all the words on the command
+/// line, where the last one is being completed. Each completion is a single
+/// word, because the price command takes its query as multiple args.
+fn item_completions(items: &[Item], words: &[String]) -> BTreeSet<String> {
+    let query_words = match words
+        .iter()
+        .position(|word| word == "price" || word == "ge")
+    {
+        Some(index) => &words[index + 1..],
+        None => return BTreeSet::new(),
+    };
+
+    // Don't try to complete flags
+    match query_words.last() {
+        Some(current) if !current.starts_with('-') => {}
+        _ => return BTreeSet::new(),
+    }
+
+    let prefix = query_words.join(" ").to_lowercase();
+    let word_index = query_words.len() - 1;
+    items
+        .iter()
+        .filter(|item| item.name.to_lowercase().starts_with(&prefix))
+        .filter_map(|item| item.name.split(' ').nth(word_index))
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str) -> Item {
+        Item {
+            id: 0,
+            name: name.into(),
+            examine: String::new(),
+            members: true,
+            low_alch: None,
+            high_alch: None,
+            limit: None,
+            value: 0,
+        }
+    }
+
+    fn complete(words: &[&str]) -> Vec<String> {
+        let items = [
+            item("Bandos godsword"),
+            item("Bandos godsword ornament kit"),
+            item("Bandos chestplate"),
+            item("Shark"),
+        ];
+        let words: Vec<String> = words.iter().map(|s| s.to_string()).collect();
+        item_completions(&items, &words).into_iter().collect()
+    }
+
+    #[test]
+    fn test_item_completions() {
+        assert_eq!(complete(&["price", "ban"]), vec!["Bandos"]);
+        assert_eq!(
+            complete(&["osrs", "--offline", "ge", "bandos", ""]),
+            vec!["chestplate", "godsword"]
+        );
+        assert_eq!(
+            complete(&["price", "bandos", "godsword", "o"]),
+            vec!["ornament"]
+        );
+        assert_eq!(complete(&["price", "sh"]), vec!["Shark"]);
+    }
+
+    #[test]
+    fn test_item_completions_none() {
+        // Not a price command
+        assert!(complete(&["hiscore", "ban"]).is_empty());
+        // Nothing typed after the subcommand yet
+        assert!(complete(&["price"]).is_empty());
+        // Completing a flag
+        assert!(complete(&["price", "--"]).is_empty());
+        // No matches
+        assert!(complete(&["price", "zzz"]).is_empty());
+    }
+}
@@ -5,7 +5,12 @@ use structopt::StructOpt;
 
 /// Get the current configuration values.
 #[derive(Debug, StructOpt)]
-pub struct ConfigGetCommand {}
+pub struct ConfigGetCommand {
+    /// Only get the value of this field. Use a dotted key (e.g.
+    /// `aliases.kc`) to get a value inside a table. If not given, the entire
+    /// config is printed.
+    pub key: Option<String>,
+}
 
 #[async_trait(?Send)]
 impl<O: Write> Command<O> for ConfigGetCommand {
@@ -16,11 +21,13 @@ impl<O: Write> Command<O> for ConfigGetCommand {
     where
         O: 'async_trait,
     {
-        // This serialization shouldn't ever fail, so if it does we want to
-        // panic
-        context.println(
-            &serde_json::to_string_pretty(context.config()).unwrap(),
-        )?;
+        let value = match &self.key {
+            Some(key) => context.config().value(key)?,
+            // This serialization shouldn't ever fail, so if it does we want
+            // to panic
+            None => serde_json::to_value(context.config()).unwrap(),
+        };
+        context.println(&serde_json::to_string_pretty(&value).unwrap())?;
         Ok(())
     }
 }
@@ -0,0 +1,59 @@
+use crate::{
+    commands::Command, config::OsrsConfig, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::io::Write;
+use structopt::StructOpt;
+
+/// List all available configuration keys, with a description of each one.
+#[derive(Debug, StructOpt)]
+pub struct ConfigKeysCommand {}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ConfigKeysCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        let rows: Vec<KeyRow> = OsrsConfig::FIELDS
+            .iter()
+            .map(|field| KeyRow {
+                key: field.key,
+                type_name: field.type_name(),
+                default: field.default_value().to_string(),
+                doc: field.doc(),
+            })
+            .collect();
+
+        if context.format().is_machine() {
+            return context.print_records(&rows);
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                context.println("")?;
+            }
+            context
+                .println_fmt(format_args!("{} ({})", row.key, row.type_name))?;
+            context.println_fmt(format_args!("    {}", row.doc))?;
+            context
+                .println_fmt(format_args!("    Default: {}", row.default))?;
+        }
+        Ok(())
+    }
+}
+
+/// Machine-readable description of a config key
+#[derive(Debug, Serialize)]
+struct KeyRow {
+    key: &'static str,
+    #[serde(rename = "type")]
+    type_name: String,
+    /// Default value, encoded as JSON
+    default: String,
+    doc: String,
+}
@@ -1,6 +1,10 @@
 use crate::{
     commands::{
-        config::{get::ConfigGetCommand, set::ConfigSetCommand},
+        config::{
+            get::ConfigGetCommand, keys::ConfigKeysCommand,
+            set::ConfigSetCommand, unset::ConfigUnsetCommand,
+            use_profile::ConfigUseCommand,
+        },
         Command, CommandType,
     },
     utils::context::CommandContext,
@@ -10,24 +14,33 @@ use std::io::Write;
 use structopt::StructOpt;
 
 mod get;
+mod keys;
 mod set;
+mod unset;
+mod use_profile;
 
 #[derive(Debug, StructOpt)]
 pub enum ConfigCommandType {
     Get(ConfigGetCommand),
+    Keys(ConfigKeysCommand),
     Set(ConfigSetCommand),
+    Unset(ConfigUnsetCommand),
+    Use(ConfigUseCommand),
 }
 
 impl<O: Write> CommandType<O> for ConfigCommandType {
     fn command(&self) -> &dyn Command<O> {
         match &self {
             Self::Get(cmd) => cmd,
+            Self::Keys(cmd) => cmd,
             Self::Set(cmd) => cmd,
+            Self::Unset(cmd) => cmd,
+            Self::Use(cmd) => cmd,
         }
     }
 }
 
-/// Get and set configuration values.
+/// Get, set and document configuration values.
 #[derive(Debug, StructOpt)]
 pub struct ConfigCommand {
     #[structopt(subcommand)]
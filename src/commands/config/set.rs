@@ -1,17 +1,21 @@
 use crate::{
-    commands::Command, config::OsrsConfig, utils::context::CommandContext,
+    commands::Command,
+    config::OsrsConfig,
+    utils::{alias, context::CommandContext},
 };
 use async_trait::async_trait;
-use figment::{providers::Serialized, Figment};
 use std::io::Write;
 use structopt::StructOpt;
 
-/// Set a configuration value
+/// Set a configuration value. Run `osrs config keys` to see all available keys.
 #[derive(Debug, StructOpt)]
 pub struct ConfigSetCommand {
-    /// The key for the config field to set
+    /// The key for the config field to set. Use dots to set a key within a
+    /// table, e.g. `table.key`.
     pub key: String,
-    /// The new value to use for the field
+    /// The new value to use for the field. Numbers, booleans, lists and tables
+    /// can be given as JSON, e.g. `true`, `[1, 2]` or `{"a": 1}`. Anything
+    /// else is treated as a string.
     pub value: String,
 }
 
@@ -24,26 +28,27 @@ impl<O: Write> Command<O> for ConfigSetCommand {
     where
         O: 'async_trait,
     {
-        // Update the given field in the config
-        let current_cfg_value = context.config();
-        let new_cfg_value: OsrsConfig =
-            Figment::from(Serialized::defaults(current_cfg_value))
-                .merge((&self.key, self.value.as_str()))
-                .extract()?;
+        // Update the given field in the stored config. We don't want to use
+        // the config from the context, because that includes overrides from
+        // the environment, which shouldn't be saved.
+        let current_cfg_value = OsrsConfig::load_stored(context.config_path())?;
+        let new_cfg_value =
+            current_cfg_value.with_value(&self.key, &self.value)?;
+        for name in new_cfg_value.aliases.keys() {
+            alias::validate_name(name)?;
+        }
 
-        // If the user didn't make any changes, then don't do anything. This
-        // is mostly to prevent a success message when they put in a bogus key.
-        if &new_cfg_value != current_cfg_value {
-            new_cfg_value.save()?;
+        if new_cfg_value != current_cfg_value {
+            new_cfg_value.save(context.config_path())?;
             context.println_fmt(format_args!(
                 "Set {} = {}",
                 self.key, self.value
             ))?;
         } else {
-            context.println(
-                "No changes. \
-                Try `osrs config get` to see available keys & current settings."
-            )?;
+            context.println_fmt(format_args!(
+                "No changes, {} is already {}",
+                self.key, self.value
+            ))?;
         }
 
         Ok(())
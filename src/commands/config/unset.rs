@@ -0,0 +1,43 @@
+use crate::{
+    commands::Command, config::OsrsConfig, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::io::Write;
+use structopt::StructOpt;
+
+/// Remove a configuration value. Top-level fields are reset to their default
+/// value, and keys within a table are removed from the table.
+#[derive(Debug, StructOpt)]
+pub struct ConfigUnsetCommand {
+    /// The key for the config field to remove. Use dots to remove a key within
+    /// a table, e.g. `table.key`.
+    pub key: String,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ConfigUnsetCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        // Modify the stored config, so overrides from the environment don't
+        // get saved
+        let current_cfg_value = OsrsConfig::load_stored(context.config_path())?;
+        let new_cfg_value = current_cfg_value.without_value(&self.key)?;
+
+        if new_cfg_value != current_cfg_value {
+            new_cfg_value.save(context.config_path())?;
+            context.println_fmt(format_args!("Unset {}", self.key))?;
+        } else {
+            context.println_fmt(format_args!(
+                "No changes, {} is not set",
+                self.key
+            ))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    commands::Command, config::OsrsConfig, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::io::Write;
+use structopt::StructOpt;
+
+/// Switch the active profile. The profile's values will be used for all
+/// subsequent commands, unless `--profile` is given.
+#[derive(Debug, StructOpt)]
+pub struct ConfigUseCommand {
+    /// Name of the profile to activate. Profiles are defined under the
+    /// `profiles` config key.
+    pub name: String,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ConfigUseCommand {
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        // Modify the stored config, so overrides from the environment don't
+        // get saved
+        let mut config = OsrsConfig::load_stored(context.config_path())?;
+        let profile = config.profile(&self.name)?.clone();
+        config.active_profile = Some(self.name.clone());
+        config.save(context.config_path())?;
+
+        context.println_fmt(format_args!(
+            "Using profile {}{}",
+            self.name,
+            match &profile.default_player {
+                Some(player) => format!(" (player: {})", player),
+                None => String::new(),
+            }
+        ))?;
+        Ok(())
+    }
+}
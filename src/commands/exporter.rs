@@ -0,0 +1,415 @@
+use crate::{
+    commands::Command, error::OsrsError, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::{io::Write, net::IpAddr};
+use structopt::StructOpt;
+#[cfg(not(target_family = "wasm"))]
+use {
+    crate::{
+        config::OsrsConfig,
+        hiscore::HiscorePlayer,
+        prices::{self, ItemWithPrice},
+        utils::trace,
+    },
+    anyhow::Context,
+    std::{
+        collections::BTreeMap,
+        fmt::Write as _,
+        net::SocketAddr,
+        sync::Arc,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tiny_http::{Header, Request, Response, Server},
+};
+
+/// Serve metrics in the Prometheus/OpenMetrics text format, e.g. for graphing
+/// in Grafana. Exports Grand Exchange prices for the items in the
+/// `exporter_items` config field, and hiscores for the players in
+/// `exporter_players`. Data is refreshed every `exporter_interval_secs`, and
+/// served at `/metrics`.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+#[derive(Debug, StructOpt)]
+pub struct ExporterCommand {
+    /// Port to listen on
+    #[structopt(short, long, default_value = "9464")]
+    port: u16,
+
+    /// Address to listen on. By default, only requests from this machine are
+    /// accepted. Use `0.0.0.0` to accept requests from other machines.
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: IpAddr,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ExporterCommand {
+    // Native implementation
+    #[cfg(not(target_family = "wasm"))]
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        let config = context.config();
+        if config.exporter_items.is_empty()
+            && config.exporter_players.is_empty()
+        {
+            return Err(OsrsError::ArgsError(
+                "Nothing to export. Set `exporter_items` and/or \
+                `exporter_players` in the config"
+                    .into(),
+            )
+            .into());
+        }
+        let interval =
+            Duration::from_secs(config.exporter_interval_secs.max(1));
+
+        let addr: SocketAddr = (self.host, self.port).into();
+        let server = Server::http(addr)
+            .map_err(|error| anyhow::anyhow!(error))
+            .with_context(|| format!("Error listening on {}", addr))?;
+        let server = Arc::new(server);
+        context.println_fmt(format_args!(
+            "Serving metrics at http://{}/metrics",
+            addr
+        ))?;
+        context.output.flush()?;
+
+        let config = context.config();
+        let mut metrics = Metrics::default();
+        metrics.refresh(config).await;
+        let mut next_refresh = Instant::now() + interval;
+        // Requests are handled in between refreshes, one at a time
+        loop {
+            let timeout =
+                next_refresh.saturating_duration_since(Instant::now());
+            let server = Arc::clone(&server);
+            let request = tokio::task::spawn_blocking(move || {
+                server.recv_timeout(timeout)
+            })
+            .await?
+            .context("Error receiving request")?;
+            if let Some(request) = request {
+                respond(request, &metrics);
+            }
+
+            if Instant::now() >= next_refresh {
+                metrics.refresh(config).await;
+                next_refresh = Instant::now() + interval;
+            }
+        }
+    }
+
+    // Browser implementation
+    #[cfg(target_family = "wasm")]
+    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        Err(OsrsError::UnsupportedEnvironment(
+            "Can't run a server in the browser".into(),
+        )
+        .into())
+    }
+}
+
+/// Send the current metrics, or a 404 for any path other than `/metrics`
+#[cfg(not(target_family = "wasm"))]
+fn respond(request: Request, metrics: &Metrics) {
+    let path = request.url().split('?').next().unwrap_or_default();
+    let response = if path == "/metrics" {
+        // This header is always valid
+        let content_type = Header::from_bytes(
+            "Content-Type",
+            "text/plain; version=0.0.4; charset=utf-8",
+        )
+        .unwrap();
+        Response::from_string(metrics.render()).with_header(content_type)
+    } else {
+        Response::from_string("Not found, metrics are at /metrics\n")
+            .with_status_code(404)
+    };
+    trace::trace(format_args!(
+        "{} {} -> {}",
+        request.method(),
+        request.url(),
+        response.status_code().0
+    ));
+    if let Err(error) = request.respond(response) {
+        // The client went away, which shouldn't take down the server
+        trace::trace(format_args!("Error sending response: {}", error));
+    }
+}
+
+/// The latest data for everything that's exported
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug, Default)]
+struct Metrics {
+    /// Items with their latest prices
+    items: Vec<ItemWithPrice>,
+    /// Latest hiscores for each player
+    players: BTreeMap<String, HiscorePlayer>,
+    /// Number of data sources (prices or a player) that failed to refresh
+    errors: u64,
+    /// When the last refresh finished, as a Unix timestamp in seconds
+    last_refresh: Option<u64>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Metrics {
+    /// Load the latest data for every item and player in the config. If
+    /// anything fails to load, the error is logged and the previous data is
+    /// kept, so one bad player doesn't take down the whole exporter.
+    async fn refresh(&mut self, config: &OsrsConfig) {
+        if !config.exporter_items.is_empty() {
+            let result = async {
+                // Prices are only loaded once per process otherwise
+                prices::refresh_prices(config).await?;
+                prices::prices_by_id(config, &config.exporter_items).await
+            }
+            .await;
+            match result {
+                Ok(items) => self.items = items,
+                Err(error) => self.log_error("prices", error),
+            }
+        }
+
+        // Players that were removed from the config since the last refresh
+        // shouldn't hang around
+        self.players
+            .retain(|player, _| config.exporter_players.contains(player));
+        for player in &config.exporter_players {
+            match HiscorePlayer::load(config, player, config.account_type).await
+            {
+                Ok(data) => {
+                    self.players.insert(player.clone(), data);
+                }
+                Err(error) => {
+                    self.log_error(&format!("hiscores for {}", player), error)
+                }
+            }
+        }
+
+        self.last_refresh = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|duration| duration.as_secs());
+    }
+
+    fn log_error(&mut self, source: &str, error: anyhow::Error) {
+        self.errors += 1;
+        eprintln!("Error refreshing {}: {:#}", source, error);
+    }
+
+    /// Render all metrics in the Prometheus text format
+    fn render(&self) -> String {
+        let mut output = String::new();
+        let mut family =
+            |name: &str,
+             help: &str,
+             kind: &str,
+             samples: Vec<(String, String)>| {
+                // Writing to a string can't fail
+                writeln!(output, "# HELP {} {}", name, help).unwrap();
+                writeln!(output, "# TYPE {} {}", name, kind).unwrap();
+                for (labels, value) in samples {
+                    writeln!(output, "{}{} {}", name, labels, value).unwrap();
+                }
+            };
+
+        let item_samples = |get: fn(&ItemWithPrice) -> Option<usize>| {
+            self.items
+                .iter()
+                .filter_map(|item| {
+                    let labels = labels(&[
+                        ("item_id", &item.item.id.to_string()),
+                        ("item", &item.item.name),
+                    ]);
+                    Some((labels, get(item)?.to_string()))
+                })
+                .collect()
+        };
+        family(
+            "osrs_item_price_high",
+            "Most recent instant-buy price on the Grand Exchange, in coins",
+            "gauge",
+            item_samples(|item| item.price?.high),
+        );
+        family(
+            "osrs_item_price_low",
+            "Most recent instant-sell price on the Grand Exchange, in coins",
+            "gauge",
+            item_samples(|item| item.price?.low),
+        );
+
+        let skill_samples = |get: fn(isize, usize, usize) -> Option<String>| {
+            self.players
+                .iter()
+                .flat_map(|(player, data)| {
+                    data.skills.iter().filter_map(move |skill| {
+                        let labels = labels(&[
+                            ("player", player),
+                            ("skill", &skill.name.to_string()),
+                        ]);
+                        Some((labels, get(skill.rank, skill.level, skill.xp)?))
+                    })
+                })
+                .collect()
+        };
+        family(
+            "osrs_player_skill_xp",
+            "Experience in a skill",
+            "gauge",
+            skill_samples(|_, _, xp| Some(xp.to_string())),
+        );
+        family(
+            "osrs_player_skill_level",
+            "Level in a skill",
+            "gauge",
+            skill_samples(|_, level, _| Some(level.to_string())),
+        );
+        family(
+            "osrs_player_skill_rank",
+            "Hiscores rank in a skill. Missing if unranked",
+            "gauge",
+            skill_samples(|rank, _, _| (rank >= 0).then(|| rank.to_string())),
+        );
+
+        // Unranked activities have -1 for both rank and score, which isn't a
+        // real value, so they're left out entirely
+        let activity_samples = |get: fn(isize, isize) -> String| {
+            self.players
+                .iter()
+                .flat_map(|(player, data)| {
+                    data.activities
+                        .iter()
+                        .filter(|activity| {
+                            activity.rank >= 0 && activity.score >= 0
+                        })
+                        .map(move |activity| {
+                            let labels = labels(&[
+                                ("player", player),
+                                ("activity", &activity.name),
+                            ]);
+                            (labels, get(activity.rank, activity.score))
+                        })
+                })
+                .collect()
+        };
+        family(
+            "osrs_player_activity_score",
+            "Score, completion count or kill count for a minigame or boss. \
+            Missing if unranked",
+            "gauge",
+            activity_samples(|_, score| score.to_string()),
+        );
+        family(
+            "osrs_player_activity_rank",
+            "Hiscores rank for a minigame or boss. Missing if unranked",
+            "gauge",
+            activity_samples(|rank, _| rank.to_string()),
+        );
+
+        family(
+            "osrs_exporter_refresh_errors_total",
+            "Number of times a data source failed to refresh",
+            "counter",
+            vec![(String::new(), self.errors.to_string())],
+        );
+        family(
+            "osrs_exporter_last_refresh_timestamp_seconds",
+            "When data was last refreshed, as a Unix timestamp",
+            "gauge",
+            self.last_refresh
+                .map(|timestamp| (String::new(), timestamp.to_string()))
+                .into_iter()
+                .collect(),
+        );
+        output
+    }
+}
+
+/// Format a set of metric labels, e.g. `{player="Lynx Titan",skill="Attack"}`
+#[cfg(not(target_family = "wasm"))]
+fn labels(labels: &[(&str, &str)]) -> String {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::transport;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(
+            labels(&[("player", "Lynx Titan"), ("skill", "Attack")]),
+            r#"{player="Lynx Titan",skill="Attack"}"#
+        );
+        assert_eq!(
+            labels(&[("item", "Weird \"item\"\\\n")]),
+            r#"{item="Weird \"item\"\\\n"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        transport::replay_test_fixtures();
+        let config = OsrsConfig {
+            exporter_items: vec![385],
+            exporter_players: vec!["Lynx Titan".into()],
+            ..Default::default()
+        };
+        let mut metrics = Metrics::default();
+        metrics.refresh(&config).await;
+        let output = metrics.render();
+        let lines: Vec<&str> = output.lines().collect();
+
+        for line in [
+            "# TYPE osrs_item_price_high gauge",
+            r#"osrs_item_price_high{item_id="385",item="Shark"} 1010"#,
+            r#"osrs_player_skill_xp{player="Lynx Titan",skill="Overall"} 4800000000"#,
+            r#"osrs_player_skill_level{player="Lynx Titan",skill="Attack"} 99"#,
+            r#"osrs_player_skill_rank{player="Lynx Titan",skill="Overall"} 1"#,
+            r#"osrs_player_activity_score{player="Lynx Titan",activity="Vorkath"} 1500"#,
+            r#"osrs_player_activity_rank{player="Lynx Titan",activity="Vorkath"} 12345"#,
+            "osrs_exporter_refresh_errors_total 0",
+        ] {
+            assert!(lines.contains(&line), "Missing line: {}", line);
+        }
+        // Unranked activities aren't exported with the -1 placeholder
+        assert!(
+            !lines
+                .iter()
+                .any(|line| line.contains("activity=\"Zulrah\"")),
+            "{}",
+            output
+        );
+        // Every sample has a value
+        for line in lines.iter().filter(|line| !line.starts_with('#')) {
+            assert!(line.rsplit(' ').next().unwrap().parse::<f64>().is_ok());
+        }
+
+        // Failures are counted, and old data is kept
+        let config = OsrsConfig {
+            exporter_players: vec!["Lynx Titan".into(), "nobody".into()],
+            ..config
+        };
+        metrics.refresh(&config).await;
+        assert_eq!(metrics.errors, 1);
+        assert_eq!(metrics.items.len(), 1);
+        assert!(metrics.players.contains_key("Lynx Titan"));
+    }
+}
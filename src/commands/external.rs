@@ -0,0 +1,64 @@
+use crate::{
+    commands::Command, unknown_command, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::io::Write;
+
+/// A command that isn't built in, as its name followed by its args. structopt
+/// only parses external subcommands into a plain list of args, so the command
+/// is implemented on that. Aliases are already expanded during parsing, so
+/// this runs a plugin.
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for Vec<String> {
+    // Native implementation
+    #[cfg(not(target_family = "wasm"))]
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        use crate::utils::plugin;
+
+        // The first arg is the command name, which is never empty
+        let (name, args) = self.split_first().unwrap();
+        // The plugin was found during parsing, but check again in case it's
+        // been removed since (e.g. in a long shell session)
+        let executable = plugin::find(name)
+            .ok_or_else(|| unknown_command(name, &context.config().aliases))?;
+        plugin::run(name, &executable, args, &mut context)
+    }
+
+    // Browser implementation
+    #[cfg(target_family = "wasm")]
+    async fn execute(&self, context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        // Plugins aren't supported, so this was never a valid command
+        Err(unknown_command(&self[0], &context.config().aliases).into())
+    }
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::output::OutputFormat;
+
+    /// A plugin that's been removed since parsing is an unknown command
+    #[tokio::test]
+    async fn test_missing_plugin() {
+        let mut output = Vec::new();
+        let context = CommandContext::for_test(&mut output, OutputFormat::Text);
+        let command = vec!["not-a-plugin".to_owned(), "--all".to_owned()];
+        let error = command.execute(context).await.unwrap_err().to_string();
+        assert!(
+            error.starts_with(
+                "Argument error: Unknown command \"not-a-plugin\"."
+            ),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
     commands::Command,
-    utils::{
-        context::CommandContext, fmt, hiscore::HiscorePlayer, table::TableExt,
-    },
+    hiscore::HiscorePlayer,
+    utils::{context::CommandContext, fmt, table::TableExt},
 };
 use async_trait::async_trait;
-use comfy_table::{presets, CellAlignment, Table};
+use comfy_table::{CellAlignment, Table};
+use serde::Serialize;
 use std::io::Write;
 use structopt::StructOpt;
 
@@ -30,19 +30,43 @@ impl<O: Write> Command<O> for HiscoreCommand {
             HiscorePlayer::load_from_args(context.config(), &self.username)
                 .await?;
 
+        // Machine-readable output gets skills and activities in one list, so
+        // that every row has the same shape
+        if context.format().is_machine() {
+            let rows: Vec<HiscoreRow> = player
+                .skills
+                .iter()
+                .map(|skill| HiscoreRow {
+                    category: "skill",
+                    name: skill.name.to_string(),
+                    rank: skill.rank,
+                    level: Some(skill.level),
+                    xp: Some(skill.xp),
+                    score: None,
+                })
+                .chain(player.activities.iter().map(|activity| HiscoreRow {
+                    category: "activity",
+                    name: activity.name.clone(),
+                    rank: activity.rank,
+                    level: None,
+                    xp: None,
+                    score: Some(activity.score),
+                }))
+                .collect();
+            return context.print_records(&rows);
+        }
+
         // Print a table for skills
         context.println("Skills")?;
         let mut table = Table::new();
-        table
-            .load_preset(presets::ASCII_BORDERS_ONLY_CONDENSED)
-            .set_aligned_header([
-                ("Skill", CellAlignment::Left),
-                ("Rank", CellAlignment::Right),
-                ("Level", CellAlignment::Right),
-                ("XP", CellAlignment::Right),
-            ]);
+        table.set_aligned_header([
+            ("Skill", CellAlignment::Left),
+            ("Rank", CellAlignment::Right),
+            ("Level", CellAlignment::Right),
+            ("XP", CellAlignment::Right),
+        ]);
         for col in [1, 2, 3] {
-            let column = table.get_column_mut(col).unwrap();
+            let column = table.column_mut(col).unwrap();
             column.set_cell_alignment(CellAlignment::Right);
         }
         for skill in player.skills {
@@ -53,19 +77,17 @@ impl<O: Write> Command<O> for HiscoreCommand {
                 fmt::fmt_int(&skill.xp),
             ]);
         }
-        context.print_table(&table)?;
+        context.print_table(&mut table)?;
         context.println("")?;
 
         // Print a table for minigames/bosses/etc.
         context.println("Minigames")?;
         let mut table = Table::new();
-        table
-            .load_preset(presets::ASCII_BORDERS_ONLY_CONDENSED)
-            .set_aligned_header([
-                ("Minigame", CellAlignment::Left),
-                ("Rank", CellAlignment::Right),
-                ("Score", CellAlignment::Right),
-            ]);
+        table.set_aligned_header([
+            ("Minigame", CellAlignment::Left),
+            ("Rank", CellAlignment::Right),
+            ("Score", CellAlignment::Right),
+        ]);
         for minigame in player.activities {
             table.add_row(vec![
                 minigame.name,
@@ -73,8 +95,86 @@ impl<O: Write> Command<O> for HiscoreCommand {
                 fmt::fmt_int(&minigame.score),
             ]);
         }
-        context.print_table(&table)?;
+        context.print_table(&mut table)?;
 
         Ok(())
     }
 }
+
+/// One row of machine-readable hiscore output. Skills populate `level` and
+/// `xp`, while activities populate `score`.
+#[derive(Debug, Serialize)]
+struct HiscoreRow {
+    /// Either `skill` or `activity`
+    category: &'static str,
+    name: String,
+    rank: isize,
+    level: Option<usize>,
+    xp: Option<usize>,
+    score: Option<isize>,
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::{output::OutputFormat, transport};
+
+    async fn run(format: OutputFormat) -> String {
+        run_with_width(format, None).await
+    }
+
+    async fn run_with_width(
+        format: OutputFormat,
+        width: Option<u16>,
+    ) -> String {
+        transport::replay_test_fixtures();
+        let mut output = Vec::new();
+        let command = HiscoreCommand {
+            username: vec!["Lynx".into(), "Titan".into()],
+        };
+        let mut context = CommandContext::for_test(&mut output, format);
+        context.width = width;
+        command.execute(context).await.unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hiscore_text() {
+        let output = run(OutputFormat::Text).await;
+        assert!(output.starts_with("Skills\n"));
+        assert!(
+            output.contains("| Overall           1   2,376   4,800,000,000 |")
+        );
+        assert!(output.contains("\nMinigames\n"));
+        assert!(output.contains("| Vorkath              12,345   1,500 |"));
+        // Activities with no history are hidden
+        assert!(!output.contains("Zulrah"));
+    }
+
+    /// Tables should be wrapped to fit a narrow terminal
+    #[tokio::test]
+    async fn test_hiscore_narrow() {
+        let output = run_with_width(OutputFormat::Text, Some(40)).await;
+        for line in output.lines() {
+            assert!(line.chars().count() <= 40, "Line too wide: {:?}", line);
+        }
+        // Long values are wrapped rather than cut off
+        assert!(output.contains("4,800,000"));
+    }
+
+    #[tokio::test]
+    async fn test_hiscore_csv() {
+        let output = run(OutputFormat::Csv).await;
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "category,name,rank,level,xp,score");
+        assert_eq!(lines[1], "skill,Overall,1,2376,4800000000,");
+        assert_eq!(lines[2], "skill,Attack,1,99,200000000,");
+        assert_eq!(
+            &lines[lines.len() - 2..],
+            &[
+                "activity,Clue Scrolls (all),54321,,,123",
+                "activity,Vorkath,12345,,,1500"
+            ]
+        );
+    }
+}
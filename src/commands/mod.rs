@@ -1,15 +1,28 @@
+mod cache;
 mod calc;
+mod completions;
 mod config;
+mod exporter;
+mod external;
 mod hiscore;
 mod ping;
 mod price;
+mod run;
+mod serve;
+mod shell;
 mod wiki;
 
 pub use self::config::*;
+pub use cache::*;
 pub use calc::*;
+pub use completions::*;
+pub use exporter::*;
 pub use hiscore::*;
 pub use ping::*;
 pub use price::*;
+pub use run::*;
+pub use serve::*;
+pub use shell::*;
 pub use wiki::*;
 
 use crate::utils::context::CommandContext;
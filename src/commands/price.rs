@@ -1,14 +1,11 @@
 use crate::{
     commands::Command,
-    utils::{
-        context::CommandContext,
-        fmt,
-        item::{ItemPrice, WIKI_ITEM_CLIENT},
-        table::TableExt,
-    },
+    prices::{self, Item, ItemPrice},
+    utils::{context::CommandContext, fmt, table::TableExt},
 };
 use async_trait::async_trait;
-use comfy_table::{presets, CellAlignment, Table};
+use comfy_table::{CellAlignment, Table};
+use serde::Serialize;
 use std::io::Write;
 use structopt::StructOpt;
 
@@ -18,6 +15,37 @@ pub struct PriceCommand {
     /// Item search query
     #[structopt(required = true)]
     query: Vec<String>,
+
+    /// Only show items with an average price above this many coins. If no
+    /// items match, nothing is printed, so this can be combined with
+    /// `--notify` to get an alert when a price crosses a threshold.
+    #[structopt(long, value_name = "price")]
+    alert_above: Option<usize>,
+
+    /// Only show items with an average price below this many coins. Works the
+    /// same as `--alert-above`.
+    #[structopt(long, value_name = "price")]
+    alert_below: Option<usize>,
+}
+
+impl PriceCommand {
+    /// Does this price cross the alert thresholds? Always true if there are
+    /// no thresholds.
+    fn is_alert(&self, price: &ItemPrice) -> bool {
+        if !self.has_alert() {
+            return true;
+        }
+        // An item with no price can't cross a threshold
+        let Some(avg) = price.avg() else {
+            return false;
+        };
+        self.alert_above.is_none_or(|above| avg > above)
+            && self.alert_below.is_none_or(|below| avg < below)
+    }
+
+    fn has_alert(&self) -> bool {
+        self.alert_above.is_some() || self.alert_below.is_some()
+    }
 }
 
 #[async_trait(?Send)]
@@ -30,32 +58,174 @@ impl<O: Write> Command<O> for PriceCommand {
         O: 'async_trait,
     {
         let query = self.query.join(" ");
-        let items: Vec<(String, ItemPrice)> = WIKI_ITEM_CLIENT
-            .search_prices(&query)
-            .await?
-            .into_iter()
-            // Filter out items that have no price. Unpack into a tuple here
-            // too so we can enforce that the price is populated
-            .filter_map(|item| Some((item.item.name, item.price?)))
-            .collect();
-
-        if items.is_empty() {
+        let items: Vec<(Item, ItemPrice)> =
+            prices::search_prices(context.config(), &query)
+                .await?
+                .into_iter()
+                // Filter out items that have no price. Unpack into a tuple here
+                // too so we can enforce that the price is populated
+                .filter_map(|item| Some((item.item, item.price?)))
+                .filter(|(_, price)| self.is_alert(price))
+                .collect();
+
+        // No alerts were triggered, so stay quiet
+        if items.is_empty() && self.has_alert() {
+            return Ok(());
+        }
+
+        if context.format().is_machine() {
+            let rows: Vec<PriceRow> = items
+                .iter()
+                .map(|(item, price)| PriceRow {
+                    id: item.id,
+                    name: &item.name,
+                    high: price.high,
+                    low: price.low,
+                    avg: price.avg(),
+                })
+                .collect();
+            context.print_records(&rows)?;
+        } else if items.is_empty() {
             context.println("No results")?;
         } else {
             let mut table = Table::new();
-            table
-                .load_preset(presets::ASCII_BORDERS_ONLY_CONDENSED)
-                .set_aligned_header([
-                    ("Item", CellAlignment::Left),
-                    ("Price", CellAlignment::Right),
-                ]);
-            for (name, price) in items {
-                table.add_row(vec![&name, &fmt::fmt_price(price.avg())]);
+            table.set_aligned_header([
+                ("Item", CellAlignment::Left),
+                ("Price", CellAlignment::Right),
+            ]);
+            for (item, price) in items {
+                table.add_row(vec![&item.name, &fmt::fmt_price(price.avg())]);
             }
 
-            context.print_table(&table)?;
+            context.print_table(&mut table)?;
         }
 
         Ok(())
     }
 }
+
+/// One row of machine-readable price output
+#[derive(Debug, Serialize)]
+struct PriceRow<'a> {
+    id: usize,
+    name: &'a str,
+    high: Option<usize>,
+    low: Option<usize>,
+    /// Average of the high and low prices
+    avg: Option<usize>,
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::{output::OutputFormat, transport};
+
+    async fn run(query: &str, format: OutputFormat) -> String {
+        run_command(command(query), format).await
+    }
+
+    fn command(query: &str) -> PriceCommand {
+        PriceCommand {
+            query: query.split(' ').map(String::from).collect(),
+            alert_above: None,
+            alert_below: None,
+        }
+    }
+
+    async fn run_command(
+        command: PriceCommand,
+        format: OutputFormat,
+    ) -> String {
+        transport::replay_test_fixtures();
+        let mut output = Vec::new();
+        command
+            .execute(CommandContext::for_test(&mut output, format))
+            .await
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_price_text() {
+        assert_eq!(
+            run("shark", OutputFormat::Text).await,
+            "\
++-------------------+
+| Item        Price |
++===================+
+| Shark       1,000 |
+| Raw shark     850 |
++-------------------+
+"
+        );
+        assert_eq!(run("nothing", OutputFormat::Text).await, "No results\n");
+    }
+
+    #[tokio::test]
+    async fn test_price_json() {
+        let output = run("SHARK", OutputFormat::Json).await;
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"id": 385, "name": "Shark", "high": 1010, "low": 990, "avg": 1000},
+                {"id": 6199, "name": "Raw shark", "high": 850, "low": null, "avg": 850},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_price_alert() {
+        let alert = |above, below| PriceCommand {
+            alert_above: above,
+            alert_below: below,
+            ..command("shark")
+        };
+        assert_eq!(
+            run_command(alert(Some(900), None), OutputFormat::Text).await,
+            "\
++---------------+
+| Item    Price |
++===============+
+| Shark   1,000 |
++---------------+
+"
+        );
+        let output =
+            run_command(alert(Some(800), Some(900)), OutputFormat::Json).await;
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json[0]["name"], "Raw shark");
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        // Nothing crossed the threshold, so nothing is printed
+        for format in [OutputFormat::Text, OutputFormat::Json] {
+            assert_eq!(run_command(alert(None, Some(100)), format).await, "");
+        }
+    }
+
+    #[test]
+    fn test_is_alert() {
+        let no_price = ItemPrice {
+            high: None,
+            high_time: None,
+            low: None,
+            low_time: None,
+        };
+        let price = ItemPrice {
+            high: Some(1010),
+            low: Some(990),
+            ..no_price
+        };
+        let alert = |above, below| PriceCommand {
+            alert_above: above,
+            alert_below: below,
+            ..command("shark")
+        };
+        // Without thresholds, everything is shown, even with no price
+        assert!(alert(None, None).is_alert(&price));
+        assert!(alert(None, None).is_alert(&no_price));
+        assert!(alert(Some(900), None).is_alert(&price));
+        assert!(!alert(Some(1000), None).is_alert(&price));
+        assert!(!alert(Some(900), None).is_alert(&no_price));
+        assert!(!alert(None, Some(2000)).is_alert(&no_price));
+    }
+}
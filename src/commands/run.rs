@@ -0,0 +1,212 @@
+use crate::{
+    commands::{shell::parse_words, Command},
+    error::OsrsError,
+    utils::context::CommandContext,
+    OsrsCommandType, OsrsOptions,
+};
+use async_trait::async_trait;
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+use structopt::StructOpt;
+
+/// Run a script of commands, one per line. Lines starting with `#` are
+/// comments, and args can be quoted like in a shell. Global options (e.g.
+/// `--format`) given to this command apply to every line. Config is loaded
+/// once, and data loaded by one command is re-used by later ones, same as in
+/// `osrs shell`.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+#[derive(Debug, StructOpt)]
+pub struct RunCommand {
+    /// Path to the script file. Use `-` to read from stdin
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+
+    /// Stop at the first command that fails. By default, failures are
+    /// reported and the rest of the script still runs.
+    #[structopt(long)]
+    fail_fast: bool,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for RunCommand {
+    // Native implementation
+    #[cfg(not(target_family = "wasm"))]
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        use crate::commands::shell::run_nested;
+        use anyhow::Context;
+        use std::{fs, io::Read};
+
+        let script = if self.file.as_os_str() == "-" {
+            let mut script = String::new();
+            std::io::stdin()
+                .read_to_string(&mut script)
+                .context("Error reading script from stdin")?;
+            script
+        } else {
+            fs::read_to_string(&self.file).with_context(|| {
+                format!("Error reading script `{}`", self.file.display())
+            })?
+        };
+
+        let mut total = 0;
+        let mut failed = 0;
+        for (line_number, line) in script_lines(&script) {
+            total += 1;
+            let result = match parse_script_line(line, &context.config.aliases)
+            {
+                Ok(options) => run_nested(&mut context, options).await,
+                Err(err) => Err(err),
+            }
+            .with_context(|| format!("Error on line {}", line_number));
+            context.output.flush()?;
+
+            if let Err(err) = result {
+                if self.fail_fast {
+                    return Err(err);
+                }
+                // Report the failure the same way the CLI would, then move on
+                failed += 1;
+                if context.format().is_machine() {
+                    let report = crate::ErrorReport::from(&err);
+                    // This serialization can't fail
+                    eprintln!("{}", serde_json::to_string(&report).unwrap());
+                } else {
+                    eprintln!("{:#}", err);
+                }
+            }
+        }
+
+        if failed > 0 {
+            Err(OsrsError::ScriptFailed { failed, total }.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Browser implementation
+    #[cfg(target_family = "wasm")]
+    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        Err(OsrsError::UnsupportedEnvironment(
+            "Scripts can't be run in the browser".into(),
+        )
+        .into())
+    }
+}
+
+/// Get each command line in a script, with its line number (starting at 1).
+/// Blank lines and comments are skipped.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+fn script_lines(script: &str) -> impl Iterator<Item = (usize, &str)> {
+    script
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parse one line of a script into CLI options
+#[cfg_attr(target_family = "wasm", allow(unused))]
+fn parse_script_line(
+    line: &str,
+    aliases: &BTreeMap<String, String>,
+) -> anyhow::Result<OsrsOptions> {
+    let options = parse_words(line, aliases)?;
+    if let OsrsCommandType::Shell(_) | OsrsCommandType::Run(_) = options.cmd {
+        return Err(OsrsError::ArgsError(
+            "Scripts can't start a shell or run other scripts".into(),
+        )
+        .into());
+    }
+    // A watched line would never finish, so the rest of the script would
+    // never run. The whole script can be watched instead.
+    if options.global.watch.is_some() {
+        return Err(OsrsError::ArgsError(
+            "Script lines can't use --watch. Watch the whole script with \
+            `osrs run --watch <interval>` instead"
+                .into(),
+        )
+        .into());
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::output::OutputFormat;
+
+    #[test]
+    fn test_script_lines() {
+        let script = "# Nightly report\n\
+            \n\
+            hiscore 'Lynx Titan'\n  \
+            # indented comment\n\
+            \tprice shark  \n";
+        assert_eq!(
+            script_lines(script).collect::<Vec<_>>(),
+            vec![(3, "hiscore 'Lynx Titan'"), (5, "price shark")]
+        );
+    }
+
+    #[test]
+    fn test_parse_script_line() {
+        let aliases = BTreeMap::new();
+        assert!(parse_script_line("calc drop -p 1/50 -n 10", &aliases).is_ok());
+        assert!(parse_script_line("hiscore \"Lynx Titan", &aliases).is_err());
+        assert!(parse_script_line("shell", &aliases).is_err());
+        assert!(parse_script_line("run other.txt", &aliases).is_err());
+        assert_eq!(
+            parse_script_line("price shark --watch 1m", &aliases)
+                .unwrap_err()
+                .to_string(),
+            "Argument error: Script lines can't use --watch. Watch the whole \
+            script with `osrs run --watch <interval>` instead"
+        );
+        let error = parse_script_line("not-a-command", &aliases).unwrap_err();
+        assert_eq!(crate::ErrorReport::from(&error).exit_code, 2);
+    }
+
+    #[test]
+    fn test_inherit_global_options() {
+        let aliases = BTreeMap::new();
+        let outer =
+            parse_words("--format json --offline run script.txt", &aliases)
+                .unwrap()
+                .global;
+
+        // Unset options are inherited
+        let options = parse_script_line("price shark", &aliases)
+            .unwrap()
+            .global
+            .with_defaults(&outer);
+        assert_eq!(options.format, OutputFormat::Json);
+        assert!(options.offline);
+
+        // Options given on the line win
+        let options = parse_script_line("price shark --format csv", &aliases)
+            .unwrap()
+            .global
+            .with_defaults(&outer);
+        assert_eq!(options.format, OutputFormat::Csv);
+
+        // The script as a whole is watched and notified, not each line
+        let outer =
+            parse_words("--notify clan --watch 1m run script.txt", &aliases)
+                .unwrap()
+                .global;
+        let options = parse_script_line("price shark", &aliases)
+            .unwrap()
+            .global
+            .with_defaults(&outer.for_nested());
+        assert_eq!(options.notify, None);
+        assert_eq!(options.watch, None);
+    }
+}
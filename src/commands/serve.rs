@@ -0,0 +1,368 @@
+use crate::{
+    commands::Command, error::OsrsError, utils::context::CommandContext,
+};
+use async_trait::async_trait;
+use std::{io::Write, net::IpAddr};
+use structopt::StructOpt;
+#[cfg(not(target_family = "wasm"))]
+use {
+    crate::{
+        commands::CommandType,
+        error::ErrorReport,
+        utils::{output::OutputFormat, trace},
+        OsrsCommandType, OsrsOptions,
+    },
+    anyhow::Context,
+    std::{net::SocketAddr, sync::Arc},
+    tiny_http::{Header, Method, Response, Server},
+};
+
+/// Run a local HTTP server with a JSON API for hiscore lookups, price search
+/// and the calculators. Each endpoint takes the same args as the matching
+/// command: path segments are positional args and query params are options,
+/// e.g. `/hiscore/Lynx%20Titan`, `/price/shark` or
+/// `/calc/drop?probability=1/5000&kc=300`. Responses match the command's JSON
+/// output. Errors are reported as JSON with the fields `kind`, `message` and
+/// `exit_code`. Global options (e.g. `--offline`) given to this command apply
+/// to every request.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+#[derive(Debug, StructOpt)]
+pub struct ServeCommand {
+    /// Port to listen on
+    #[structopt(short, long, default_value = "8080")]
+    port: u16,
+
+    /// Address to listen on. By default, only requests from this machine are
+    /// accepted. Use `0.0.0.0` to accept requests from other machines.
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: IpAddr,
+}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ServeCommand {
+    // Native implementation
+    #[cfg(not(target_family = "wasm"))]
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        let server = ApiServer::bind((self.host, self.port).into())?;
+        context.println_fmt(format_args!(
+            "Listening on http://{}",
+            server.addr()
+        ))?;
+        context.output.flush()?;
+        // Requests are handled one at a time. That's plenty for a local
+        // server, and it means loaded data is shared without any locking.
+        loop {
+            server.handle_next(&context).await?;
+        }
+    }
+
+    // Browser implementation
+    #[cfg(target_family = "wasm")]
+    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        Err(OsrsError::UnsupportedEnvironment(
+            "Can't run a server in the browser".into(),
+        )
+        .into())
+    }
+}
+
+/// HTTP server that runs commands for each request
+#[cfg(not(target_family = "wasm"))]
+struct ApiServer {
+    server: Arc<Server>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl ApiServer {
+    fn bind(addr: SocketAddr) -> anyhow::Result<Self> {
+        let server = Server::http(addr)
+            .map_err(|error| anyhow::anyhow!(error))
+            .with_context(|| format!("Error listening on {}", addr))?;
+        Ok(Self {
+            server: Arc::new(server),
+        })
+    }
+
+    /// The address the server is listening on. If bound to port 0, this has
+    /// the actual port.
+    fn addr(&self) -> SocketAddr {
+        // We always bind to an IP address, never a Unix socket
+        self.server.server_addr().to_ip().unwrap()
+    }
+
+    /// Wait for the next request, and respond to it. Commands are run with
+    /// the config and global options from the given context. Errors from the
+    /// command are sent to the client. Only errors with the server itself are
+    /// returned.
+    async fn handle_next<O: Write>(
+        &self,
+        context: &CommandContext<O>,
+    ) -> anyhow::Result<()> {
+        let server = Arc::clone(&self.server);
+        let request = tokio::task::spawn_blocking(move || server.recv())
+            .await?
+            .context("Error receiving request")?;
+
+        let (status, body) =
+            match run_request(context, request.method(), request.url()).await {
+                Ok(body) => (200, body),
+                Err(error) => (error.status, error.to_json()),
+            };
+        trace::trace(format_args!(
+            "{} {} -> {}",
+            request.method(),
+            request.url(),
+            status
+        ));
+
+        // This header is always valid
+        let content_type =
+            Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(error) = request.respond(response) {
+            // The client went away, which shouldn't take down the server
+            trace::trace(format_args!("Error sending response: {}", error));
+        }
+        Ok(())
+    }
+}
+
+/// An error to send back to the client
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    report: ErrorReport,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl ApiError {
+    fn not_found(url: &str) -> Self {
+        Self {
+            status: 404,
+            report: ErrorReport {
+                kind: "not_found",
+                message: format!(
+                    "Unknown endpoint `{}`. Available endpoints are \
+                    /hiscore, /price, /calc/drop, /calc/stew and /calc/xp",
+                    url
+                ),
+                exit_code: OsrsError::ARGS_EXIT_CODE,
+            },
+        }
+    }
+
+    fn to_json(&self) -> String {
+        // This serialization can't fail
+        serde_json::to_string(&self.report).unwrap() + "\n"
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        let report = ErrorReport::from(&error);
+        let status = match report.kind {
+            "invalid_args" => 400,
+            "player_not_found" | "not_cached" => 404,
+            "rate_limited" => 503,
+            "upstream_unavailable" | "invalid_response" => 502,
+            _ => 500,
+        };
+        Self { status, report }
+    }
+}
+
+/// Run the command for a request, and get its JSON output
+#[cfg(not(target_family = "wasm"))]
+async fn run_request<O: Write>(
+    context: &CommandContext<O>,
+    method: &Method,
+    url: &str,
+) -> Result<String, ApiError> {
+    if *method != Method::Get {
+        return Err(ApiError {
+            status: 405,
+            ..ApiError::from(anyhow::Error::from(OsrsError::ArgsError(
+                format!("Method {} not allowed, use GET", method),
+            )))
+        });
+    }
+
+    let options = parse_request(url)?;
+    let mut output = Vec::new();
+    let mut request_context = context.with_output(&mut output);
+    request_context.format = OutputFormat::Json;
+    options
+        .cmd
+        .command()
+        .execute(request_context)
+        .await
+        .map_err(ApiError::from)?;
+    // Our JSON output is always UTF-8
+    Ok(String::from_utf8(output).unwrap())
+}
+
+/// Convert a request URL into command options. Only commands that look up or
+/// calculate data are available, nothing that changes state. Global options
+/// in the request are parsed, but ignored.
+#[cfg(not(target_family = "wasm"))]
+fn parse_request(url: &str) -> Result<OsrsOptions, ApiError> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(decode)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let endpoint_len = match segments
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["hiscore", ..] | ["price", ..] => 1,
+        ["calc", "drop" | "stew" | "xp", ..] => 2,
+        _ => return Err(ApiError::not_found(path)),
+    };
+
+    let mut args = vec!["osrs".to_owned()];
+    args.extend(segments[..endpoint_len].iter().cloned());
+    // Query params become options. These have to go before the positional
+    // args, which come after a `--`.
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        // Query strings use + for spaces
+        let param = param.replace('+', " ");
+        match param.split_once('=') {
+            Some((key, value)) => {
+                args.push(format!("--{}", decode(key)?.replace('_', "-")));
+                args.push(decode(value)?);
+            }
+            None => args.push(format!("--{}", decode(&param)?)),
+        }
+    }
+    if segments.len() > endpoint_len {
+        // Path segments are always positional, even if they start with -
+        args.push("--".into());
+        args.extend(segments[endpoint_len..].iter().cloned());
+    }
+
+    let options = OsrsOptions::from_iter_safe(args).map_err(|error| {
+        ApiError::from(anyhow::Error::from(OsrsError::InvalidCommand(error)))
+    })?;
+    // The endpoint match should make this impossible, but be sure
+    match options.cmd {
+        OsrsCommandType::Hiscore(_)
+        | OsrsCommandType::Price(_)
+        | OsrsCommandType::Calc(_) => Ok(options),
+        _ => Err(ApiError::not_found(path)),
+    }
+}
+
+/// Decode one percent-encoded piece of a URL
+#[cfg(not(target_family = "wasm"))]
+fn decode(value: &str) -> Result<String, ApiError> {
+    urlencoding::decode(value)
+        .map(String::from)
+        .map_err(|error| {
+            ApiError::from(anyhow::Error::from(OsrsError::ArgsError(format!(
+                "Invalid URL encoding in `{}`: {}",
+                value, error
+            ))))
+        })
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::transport;
+    use serde_json::Value;
+
+    #[test]
+    fn test_parse_request() {
+        assert!(parse_request("/hiscore/Lynx%20Titan").is_ok());
+        assert!(parse_request("/hiscore/Lynx+Titan").is_ok());
+        assert!(parse_request("/price/dragon/bones").is_ok());
+        assert!(parse_request("/calc/drop?probability=1/5000&kc=300").is_ok());
+        assert!(parse_request("/calc/xp?from_lvl=50&to_lvl=60").is_ok());
+        assert!(parse_request("/calc/stew?doses=10&boost=2").is_ok());
+
+        let error = parse_request("/calc/drop?probability=1/5000").unwrap_err();
+        assert_eq!(error.status, 400);
+        assert_eq!(error.report.kind, "invalid_args");
+
+        // Anything that changes state is off limits
+        for url in [
+            "/",
+            "/config/set/default_player/Zezima",
+            "/cache/clear",
+            "/shell",
+            "/run/script.txt",
+            "/ge/shark",
+            "/calc",
+        ] {
+            let error = parse_request(url).unwrap_err();
+            assert_eq!(error.status, 404, "{}", url);
+            assert_eq!(error.report.kind, "not_found");
+        }
+    }
+
+    /// Run a real server, and hit it with a real client
+    #[tokio::test]
+    async fn test_serve() {
+        transport::replay_test_fixtures();
+        let context = CommandContext::for_test(Vec::new(), OutputFormat::Text);
+        let server = ApiServer::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let base_url = format!("http://{}", server.addr());
+        let requests = [
+            ("GET", "/price/shark", 200),
+            ("GET", "/calc/drop?probability=1%2F50&iterations=10", 200),
+            ("GET", "/calc/drop?probability=nope&iterations=10", 400),
+            ("GET", "/cache/clear", 404),
+            ("POST", "/price/shark", 405),
+        ];
+
+        let serve = async {
+            for _ in requests {
+                server.handle_next(&context).await.unwrap();
+            }
+        };
+        let client = async {
+            let client = reqwest::Client::new();
+            let mut bodies = Vec::new();
+            for (method, path, status) in requests {
+                let response = client
+                    .request(
+                        method.parse().unwrap(),
+                        format!("{}{}", base_url, path),
+                    )
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status().as_u16(), status, "{}", path);
+                let body = response.text().await.unwrap();
+                bodies.push(serde_json::from_str::<Value>(&body).unwrap());
+            }
+            bodies
+        };
+        let ((), bodies) = tokio::join!(serve, client);
+
+        assert_eq!(bodies[0][0]["name"], "Shark");
+        assert_eq!(bodies[1]["iterations"], 10);
+        assert!(bodies[1]["chance"].as_f64().unwrap() > 0.18);
+        assert_eq!(bodies[2]["kind"], "invalid_args");
+        assert_eq!(bodies[3]["kind"], "not_found");
+        assert_eq!(bodies[4]["kind"], "invalid_args");
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    commands::Command, error::OsrsError, utils::context::CommandContext,
+    OsrsCommandType, OsrsOptions,
+};
+use async_trait::async_trait;
+use std::{collections::BTreeMap, io::Write, iter};
+use structopt::StructOpt;
+
+/// Start an interactive shell, to run multiple commands in a row. Config is
+/// loaded once, and data loaded by one command (e.g. the list of items) is
+/// re-used by later commands, so repeated lookups are much faster. Global
+/// options (e.g. `--format`) given to this command apply to every line.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+#[derive(Debug, StructOpt)]
+pub struct ShellCommand {}
+
+#[async_trait(?Send)]
+impl<O: Write> Command<O> for ShellCommand {
+    // Native implementation
+    #[cfg(not(target_family = "wasm"))]
+    async fn execute(
+        &self,
+        mut context: CommandContext<O>,
+    ) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        use rustyline::{error::ReadlineError, DefaultEditor};
+
+        let mut editor = DefaultEditor::new()?;
+        let history_path = history_path();
+        // The history file won't exist on the first run, which is fine
+        let _ = editor.load_history(&history_path);
+
+        loop {
+            let line = match editor.readline("osrs> ") {
+                Ok(line) => line,
+                // Ctrl-C clears the current line
+                Err(ReadlineError::Interrupted) => continue,
+                // Ctrl-D exits
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let _ = editor.add_history_entry(line);
+            if matches!(line, "exit" | "quit") {
+                break;
+            }
+
+            // Errors are printed and then ignored, so one bad command doesn't
+            // kill the whole shell
+            let result = match parse_line(line, &context.config.aliases) {
+                Ok(options) => run_nested(&mut context, options).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                eprintln!("{:#}", err);
+            }
+            context.output.flush()?;
+        }
+
+        // Failing to save history shouldn't fail the command
+        if let Some(parent) = history_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = editor.save_history(&history_path);
+        Ok(())
+    }
+
+    // Browser implementation
+    #[cfg(target_family = "wasm")]
+    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    where
+        O: 'async_trait,
+    {
+        Err(OsrsError::UnsupportedEnvironment(
+            "The website is already a shell!".into(),
+        )
+        .into())
+    }
+}
+
+/// Run a command from a shell or script, with the config that's already loaded
+/// in the outer command's context. Global options given to the outer command
+/// are used for any options that the inner command doesn't give.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+pub(super) async fn run_nested<O: Write>(
+    context: &mut CommandContext<O>,
+    mut options: OsrsOptions,
+) -> anyhow::Result<()> {
+    options.global =
+        options.global.with_defaults(&context.options.for_nested());
+    let changes_config = matches!(options.cmd, OsrsCommandType::Config(_));
+    let config = context.config_for(&options.global)?;
+    // Erase the output type here, so that the nested command doesn't use a
+    // different output type than we do. Otherwise, the compiler would have to
+    // generate infinite nested types for shells within shells.
+    let output: &mut dyn Write = &mut context.output;
+    options.run_with_config(Some(&config), output).await?;
+    // Later commands should see any changes
+    if changes_config {
+        context.reload_config()?;
+    }
+    Ok(())
+}
+
+/// Parse one line of input into CLI options. The line is split into words
+/// using shell quoting rules, so multi-word args can be quoted. The binary
+/// name should *not* be included. The given aliases (generally from the
+/// loaded config) are expanded.
+#[cfg_attr(target_family = "wasm", allow(unused))]
+pub(super) fn parse_words(
+    line: &str,
+    aliases: &BTreeMap<String, String>,
+) -> anyhow::Result<OsrsOptions> {
+    let words = shell_words::split(line)?;
+    let options = OsrsOptions::from_iter_with_alias_map(
+        iter::once("osrs".to_owned()).chain(words),
+        aliases,
+    )?;
+    Ok(options)
+}
+
+/// Parse one line of shell input into CLI options
+#[cfg_attr(target_family = "wasm", allow(unused))]
+fn parse_line(
+    line: &str,
+    aliases: &BTreeMap<String, String>,
+) -> anyhow::Result<OsrsOptions> {
+    let options = parse_words(line, aliases)?;
+    if let OsrsCommandType::Shell(_) = options.cmd {
+        return Err(OsrsError::ArgsError(
+            "Already in a shell, can't start another one".into(),
+        )
+        .into());
+    }
+    // A watched command never finishes, so it would take over the shell
+    if options.global.watch.is_some() {
+        return Err(OsrsError::ArgsError(
+            "Commands in the shell can't use --watch".into(),
+        )
+        .into());
+    }
+    Ok(options)
+}
+
+/// Get the path to the file where we store shell history
+#[cfg(not(target_family = "wasm"))]
+fn history_path() -> std::path::PathBuf {
+    let data_dir = if cfg!(debug_assertions) {
+        // In dev mode, always use current dir so we don't pollute the fs
+        ".".into()
+    } else {
+        // Use the OS-defined data directory, if possible. If not available,
+        // fall back to the home dir, then finally current dir
+        dirs::data_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default()
+            .join("osrs-cli")
+    };
+
+    data_dir.join("osrs_history.txt")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::output::OutputFormat;
+    #[cfg(not(target_family = "wasm"))]
+    use crate::HttpMode;
+
+    #[test]
+    fn test_parse_line() {
+        let no_aliases = BTreeMap::new();
+        assert!(parse_line("calc drop -p 1/50 -n 10", &no_aliases).is_ok());
+        // Quoted args are kept together
+        let options =
+            parse_line("hiscore 'Lynx Titan' --format json", &no_aliases)
+                .unwrap();
+        assert!(format!("{:?}", options.cmd).contains("[\"Lynx Titan\"]"));
+
+        // Aliases come from the given map, not the config file
+        let aliases =
+            [("lynx".to_owned(), "hiscore 'Lynx Titan'".to_owned())].into();
+        let options = parse_line("lynx --format json", &aliases).unwrap();
+        assert!(format!("{:?}", options.cmd).contains("[\"Lynx Titan\"]"));
+        assert_eq!(options.global.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_line_errors() {
+        let no_aliases = BTreeMap::new();
+        // Unclosed quote
+        assert!(parse_line("hiscore \"Lynx Titan", &no_aliases).is_err());
+        // Invalid command
+        assert!(parse_line("not-a-command", &no_aliases).is_err());
+        // No shells within shells
+        assert_eq!(
+            parse_line("shell", &no_aliases).unwrap_err().to_string(),
+            "Argument error: Already in a shell, can't start another one"
+        );
+        // No commands that never finish
+        assert!(parse_line("price shark --watch 1m", &no_aliases).is_err());
+    }
+
+    /// Commands run against the outer command's config and options
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_run_nested() {
+        let no_aliases = BTreeMap::new();
+        let mut output = Vec::new();
+        let mut context =
+            CommandContext::for_test(&mut output, OutputFormat::Csv);
+        // This config only exists in memory, so it has to be the one that's
+        // used
+        context.config.default_player = Some("Lynx Titan".into());
+        context.config.http_mode = HttpMode::Replay;
+        context.config.http_fixtures = Some(
+            [env!("CARGO_MANIFEST_DIR"), "test_data", "http"]
+                .iter()
+                .collect(),
+        );
+
+        run_nested(&mut context, parse_line("hiscore", &no_aliases).unwrap())
+            .await
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "category,name,rank,level,xp,score");
+        assert_eq!(lines[1], "skill,Overall,1,2376,4800000000,");
+    }
+}
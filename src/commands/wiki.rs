@@ -1,5 +1,6 @@
 use crate::{
     commands::Command,
+    config::OsrsConfig,
     utils::{context::CommandContext, http},
 };
 use async_trait::async_trait;
@@ -17,17 +18,17 @@ pub struct WikiCommand {
 impl<O: Write> Command<O> for WikiCommand {
     // Native implementation
     #[cfg(not(target_family = "wasm"))]
-    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    async fn execute(&self, context: CommandContext<O>) -> anyhow::Result<()>
     where
         O: 'async_trait,
     {
-        open::that(self.url())?;
+        open::that(self.url(context.config()))?;
         Ok(())
     }
 
     // Browser implementation
     #[cfg(target_family = "wasm")]
-    async fn execute(&self, _context: CommandContext<O>) -> anyhow::Result<()>
+    async fn execute(&self, context: CommandContext<O>) -> anyhow::Result<()>
     where
         O: 'async_trait,
     {
@@ -36,7 +37,7 @@ impl<O: Write> Command<O> for WikiCommand {
         // Open in a new tab
         browser::window()?
             .open_with_url_and_target(
-                &self.url(),
+                &self.url(context.config()),
                 // Set target="..." to a descriptive value, so if the user
                 // searches the same value twice, the browser will re-use the
                 // tab.
@@ -54,10 +55,7 @@ impl WikiCommand {
     }
 
     /// Get the wiki search URL
-    fn url(&self) -> String {
-        http::url(
-            "https://oldschool.runescape.wiki/",
-            &[("search", &self.query())],
-        )
+    fn url(&self, config: &OsrsConfig) -> String {
+        http::url(&config.wiki_url, &[("search", &self.query())])
     }
 }
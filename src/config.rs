@@ -1,52 +1,586 @@
+use crate::{
+    error::OsrsError,
+    hiscore::AccountType,
+    utils::{notify::Webhook, table::TableStyle, transport::HttpMode},
+};
 use anyhow::Context;
 use figment::{
-    providers::{Format, Json, Serialized},
+    providers::{Env, Format, Json, Serialized},
     Figment,
 };
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
-pub struct OsrsConfig {
-    /// For commands that take a player name, this player will be used when
-    /// none is given.
-    pub default_player: Option<String>,
+/// Define the config struct, along with a list of metadata about each of its
+/// fields. Fields can only have doc comments, no other attributes.
+macro_rules! config_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                pub $field:ident: $type:ty,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            $(
+                $(#[doc = $doc])*
+                pub $field: $type,
+            )*
+        }
+
+        impl $name {
+            /// Metadata for every config field, in definition order
+            pub const FIELDS: &'static [ConfigField] = &[$(
+                ConfigField {
+                    key: stringify!($field),
+                    doc: &[$($doc),*],
+                    type_name: <$type as ConfigType>::type_name,
+                },
+            )*];
+        }
+    };
+}
+
+config_struct! {
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct OsrsConfig {
+        /// For commands that take a player name, this player will be used when
+        /// none is given.
+        pub default_player: Option<String>,
+
+        /// Which hiscores the default player is ranked on
+        pub account_type: AccountType,
+
+        /// Named profiles, e.g. for multiple accounts. Each profile can
+        /// override `default_player` and `account_type`. Set values with e.g.
+        /// `osrs config set profiles.iron.default_player "Iron Lynx"`.
+        pub profiles: BTreeMap<String, Profile>,
+
+        /// The profile to use when none is given with `--profile`. Switch
+        /// profiles with `osrs config use <name>`.
+        pub active_profile: Option<String>,
+
+        /// Custom command aliases, e.g. `osrs config set aliases.kc "calc drop
+        /// -p $1 --kc $2"`. `$1`, `$2`, etc. are replaced with the args given
+        /// to the alias, and `$@` with any remaining args. Remaining args are
+        /// appended if there's no `$@`.
+        pub aliases: BTreeMap<String, String>,
+
+        /// How tables are drawn in text output. `discord` wraps tables in code
+        /// blocks, split to fit in Discord messages.
+        pub table_style: TableStyle,
+
+        /// Webhooks that command output can be sent to with `--notify <name>`,
+        /// e.g. `osrs config set webhooks.clan '{"url": "https://...",
+        /// "kind": "discord"}'`. `kind` is `json` (the default) or `discord`.
+        pub webhooks: BTreeMap<String, Webhook>,
+
+        /// Item IDs to export prices for with `osrs exporter`
+        pub exporter_items: Vec<usize>,
+
+        /// Players to export hiscores for with `osrs exporter`. Players are
+        /// looked up on the hiscores for `account_type`.
+        pub exporter_players: Vec<String>,
+
+        /// How often `osrs exporter` refreshes its data, in seconds
+        pub exporter_interval_secs: u64,
+
+        /// Where HTTP responses come from. `live` uses the network, `record`
+        /// uses the network and saves each response to `http_fixtures`, and
+        /// `replay` serves responses from `http_fixtures` without touching the
+        /// network. Recording and replaying are only supported natively.
+        pub http_mode: HttpMode,
+
+        /// Directory of recorded HTTP responses, for the `record` and `replay`
+        /// HTTP modes
+        pub http_fixtures: Option<PathBuf>,
+
+        /// Maximum time to wait for a single HTTP request, in seconds. 0 means
+        /// no limit.
+        pub http_timeout_secs: u64,
+
+        /// Number of times to retry a request that failed because of a
+        /// timeout, connection error, rate limit or server error
+        pub http_retries: u64,
+
+        /// Delay before the first retry of a failed request, in milliseconds.
+        /// The delay doubles for each subsequent retry.
+        pub http_retry_delay_ms: u64,
+
+        /// Minimum time between requests to the same host, in milliseconds, to
+        /// avoid hammering upstream services
+        pub http_min_interval_ms: u64,
+
+        /// Base URL for the OSRS hiscores API. Override this to use a proxy or
+        /// mirror.
+        pub hiscore_url: String,
+
+        /// Base URL for the OSRS Wiki's Real-time Prices API. Override this to
+        /// use a proxy or mirror.
+        pub prices_url: String,
+
+        /// Base URL for the OSRS Wiki, used for searches
+        pub wiki_url: String,
+    }
+}
+
+impl Default for OsrsConfig {
+    fn default() -> Self {
+        Self {
+            default_player: None,
+            account_type: AccountType::default(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
+            aliases: BTreeMap::new(),
+            table_style: TableStyle::default(),
+            webhooks: BTreeMap::new(),
+            exporter_items: Vec::new(),
+            exporter_players: Vec::new(),
+            exporter_interval_secs: 300,
+            http_mode: HttpMode::default(),
+            http_fixtures: None,
+            http_timeout_secs: 30,
+            http_retries: 2,
+            http_retry_delay_ms: 500,
+            http_min_interval_ms: 100,
+            hiscore_url: "https://secure.runescape.com/m=hiscore_oldschool"
+                .into(),
+            prices_url: "https://prices.runescape.wiki/api/v1/osrs".into(),
+            wiki_url: "https://oldschool.runescape.wiki/".into(),
+        }
+    }
 }
 
 impl OsrsConfig {
+    /// Prefix for environment variables that override config fields, e.g.
+    /// `OSRS_DEFAULT_PLAYER`. Use `__` to separate keys of nested tables, like
+    /// the dots used by `osrs config set`.
+    pub const ENV_PREFIX: &'static str = "OSRS_";
+
     /// Create a base Figment instance that populates the default values.
     /// Further values should be chained on by the caller
     fn figment() -> Figment {
         Figment::from(Serialized::defaults(OsrsConfig::default()))
     }
+
+    /// Load config from storage, then apply a profile and overrides from
+    /// environment variables. Any missing values will be populated with
+    /// defaults. If a path is given, config will be loaded from there instead
+    /// of the default location. If no profile is given, the active one is
+    /// used. See [Self::with_profile].
+    pub fn load(
+        path: Option<&Path>,
+        profile: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        Self::load_stored(path)?.with_overrides(profile, Self::env_vars())
+    }
+
+    /// Apply a profile, then the given environment overrides, so that an
+    /// override always beats the profile. The overrides are applied before the
+    /// profile as well, since they can also pick the active profile or define
+    /// profiles.
+    fn with_overrides(
+        self,
+        profile: Option<&str>,
+        vars: Vec<(String, String)>,
+    ) -> anyhow::Result<Self> {
+        self.with_env_overrides_from(vars.clone())?
+            .with_profile(profile)?
+            .with_env_overrides_from(vars)
+    }
+
+    /// Get overrides from `OSRS_`-prefixed environment variables, as dotted
+    /// keys and values
+    fn env_vars() -> Vec<(String, String)> {
+        Env::prefixed(Self::ENV_PREFIX)
+            .split("__")
+            .iter()
+            .map(|(key, value)| (key.as_str().to_owned(), value))
+            .collect()
+    }
+
+    /// Apply overrides from environment variables, as dotted keys and values.
+    /// Keys that don't match a config field are ignored, since other tools may
+    /// share the prefix. [Env] only finds the variables and maps their names;
+    /// the values aren't extracted through it, because it parses them without
+    /// knowing the field type, so e.g. a player named `1234` would become a
+    /// number and fail to load. Instead, values are parsed the same way as
+    /// `osrs config set`.
+    fn with_env_overrides_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> anyhow::Result<Self> {
+        vars.into_iter().try_fold(self, |config, (key, value)| {
+            if Self::field(top_level_key(&key)).is_err() {
+                return Ok(config);
+            }
+            config.with_value(&key, &value).with_context(|| {
+                OsrsError::Config(format!(
+                    "Error loading config from environment variable `{}{}`",
+                    Self::ENV_PREFIX,
+                    key.to_uppercase().replace('.', "__")
+                ))
+            })
+        })
+    }
+
+    /// Load only the config that's in storage, without any overrides from
+    /// environment variables. Use this to get a config that will be modified
+    /// and saved, so that overrides don't get saved along with it.
+    pub fn load_stored(path: Option<&Path>) -> anyhow::Result<Self> {
+        Self::stored_figment(path)?.extract().with_context(|| {
+            OsrsError::Config(format!(
+                "Error loading config from {}",
+                Self::location(path)
+            ))
+        })
+    }
+
+    /// Apply the values from a profile on top of the top-level values. If no
+    /// name is given, the active profile from the config is used, if any.
+    /// Returns an error if the given profile doesn't exist.
+    pub fn with_profile(mut self, name: Option<&str>) -> anyhow::Result<Self> {
+        let (name, profile) = match (name, &self.active_profile) {
+            (Some(name), _) => (name.to_owned(), self.profile(name)?.clone()),
+            // If the active profile was deleted, just ignore it. Otherwise
+            // every command would fail, including the ones to fix the config
+            (None, Some(name)) => match self.profiles.get(name) {
+                Some(profile) => (name.clone(), profile.clone()),
+                None => return Ok(self),
+            },
+            (None, None) => return Ok(self),
+        };
+        if let Some(default_player) = profile.default_player {
+            self.default_player = Some(default_player);
+        }
+        if let Some(account_type) = profile.account_type {
+            self.account_type = account_type;
+        }
+        self.active_profile = Some(name);
+        Ok(self)
+    }
+
+    /// Get a profile by name. Returns an error if it doesn't exist.
+    pub fn profile(&self, name: &str) -> anyhow::Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let available = if self.profiles.is_empty() {
+                "No profiles are defined".to_owned()
+            } else {
+                format!(
+                    "Available profiles: {}",
+                    self.profiles
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            OsrsError::ArgsError(format!(
+                "Unknown profile `{}`. {}",
+                name, available
+            ))
+            .into()
+        })
+    }
+
+    /// Get the metadata for a config field. Returns an error if the field
+    /// doesn't exist, with a suggestion for a similar key if there is one.
+    pub fn field(key: &str) -> anyhow::Result<&'static ConfigField> {
+        if let Some(field) = Self::FIELDS.iter().find(|field| field.key == key)
+        {
+            return Ok(field);
+        }
+
+        let mut message = format!("Unknown config key `{}`.", key);
+        let suggestion = Self::FIELDS
+            .iter()
+            .map(|field| (strsim::jaro_winkler(key, field.key), field.key))
+            .filter(|(similarity, _)| *similarity > 0.8)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some((_, suggested_key)) = suggestion {
+            message.push_str(&format!(" Did you mean `{}`?", suggested_key));
+        }
+        message.push_str(" Run `osrs config keys` to see all available keys.");
+        Err(OsrsError::ArgsError(message).into())
+    }
+
+    /// Get one value from this config, as JSON. The key can be a top-level
+    /// field, or a dotted path into a nested table (e.g. `table.key`). Keys
+    /// that aren't set within a table are `null`.
+    pub fn value(&self, key: &str) -> anyhow::Result<serde_json::Value> {
+        Self::field(top_level_key(key))?;
+        let mut value = serde_json::to_value(self)?;
+        for segment in key.split('.') {
+            let serde_json::Value::Object(mut table) = value else {
+                return Err(OsrsError::ArgsError(format!(
+                    "`{}` is not inside a table",
+                    key
+                ))
+                .into());
+            };
+            match table.remove(segment) {
+                Some(inner) => value = inner,
+                // Nothing further down can be set either
+                None => return Ok(serde_json::Value::Null),
+            }
+        }
+        Ok(value)
+    }
+
+    /// Get a copy of this config with one value changed. The key can be a
+    /// top-level field, or a dotted path into a nested table (e.g.
+    /// `table.key`). The value is parsed as JSON if possible, so numbers,
+    /// booleans, lists (`[1, 2]`) and tables (`{"a": 1}`) can be given. If it
+    /// isn't valid JSON, or JSON doesn't fit the field's type, it's treated as
+    /// a string.
+    pub fn with_value(&self, key: &str, value: &str) -> anyhow::Result<Self> {
+        let field = Self::field(top_level_key(key))?;
+        let parsed: Option<serde_json::Value> = serde_json::from_str(value)
+            .ok()
+            // Strings are handled by the fallback below
+            .filter(|value: &serde_json::Value| !value.is_string());
+        if let Some(parsed) = parsed {
+            let json = self.modified_json(key, |slot| *slot = Some(parsed))?;
+            if let Ok(config) = serde_json::from_value(json) {
+                return Ok(config);
+            }
+        }
+
+        let json =
+            self.modified_json(key, |slot| *slot = Some(value.into()))?;
+        serde_json::from_value(json).map_err(|err| {
+            OsrsError::ArgsError(format!(
+                "Invalid value for `{}` (expected {}): {}",
+                key,
+                field.type_name(),
+                err
+            ))
+            .into()
+        })
+    }
+
+    /// Get a copy of this config with one value removed. For top-level
+    /// fields, this resets the field to its default value. For keys within a
+    /// nested table, this removes the key from the table.
+    pub fn without_value(&self, key: &str) -> anyhow::Result<Self> {
+        let field = Self::field(top_level_key(key))?;
+        let json = if key.contains('.') {
+            self.modified_json(key, |slot| *slot = None)?
+        } else {
+            self.modified_json(key, |slot| *slot = Some(field.default_value()))?
+        };
+        Ok(serde_json::from_value(json)?)
+    }
+
+    /// Serialize this config to JSON, and modify the value at the given key.
+    /// The modifier gets the current value for the key (or `None` if it isn't
+    /// set), and can replace it or set it to `None` to remove it.
+    fn modified_json(
+        &self,
+        key: &str,
+        modifier: impl FnOnce(&mut Option<serde_json::Value>),
+    ) -> anyhow::Result<serde_json::Value> {
+        // Walk down to the table that holds the target key. Missing tables
+        // along the way are created.
+        let mut root = serde_json::to_value(self)?;
+        let mut path: Vec<&str> = key.split('.').collect();
+        let last = path.pop().unwrap_or_default();
+        let not_table =
+            || OsrsError::ArgsError(format!("`{}` is not inside a table", key));
+        let mut table = &mut root;
+        for segment in path {
+            table = table
+                .as_object_mut()
+                .ok_or_else(not_table)?
+                .entry(segment)
+                .or_insert(serde_json::Value::Null);
+            if table.is_null() {
+                *table = serde_json::json!({});
+            }
+        }
+        let table = table.as_object_mut().ok_or_else(not_table)?;
+
+        let mut value = table.remove(last);
+        modifier(&mut value);
+        if let Some(value) = value {
+            table.insert(last.to_owned(), value);
+        }
+        Ok(root)
+    }
+}
+
+/// Get the first segment of a dotted key, which is the name of a top-level
+/// field
+fn top_level_key(key: &str) -> &str {
+    key.split('.').next().unwrap_or_default()
+}
+
+/// A set of values that override the top-level config when active. Useful for
+/// playing multiple accounts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Overrides `default_player`
+    pub default_player: Option<String>,
+    /// Overrides `account_type`
+    pub account_type: Option<AccountType>,
+}
+
+/// Metadata about a single config field, for documentation
+#[derive(Copy, Clone, Debug)]
+pub struct ConfigField {
+    /// Name of the field
+    pub key: &'static str,
+    /// Lines of the field's doc comment
+    doc: &'static [&'static str],
+    /// Get a user-friendly name for the type of the field
+    type_name: fn() -> String,
+}
+
+impl ConfigField {
+    /// Get the field's doc comment, as a single line
+    pub fn doc(&self) -> String {
+        self.doc
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Get a user-friendly name for the type of the field
+    pub fn type_name(&self) -> String {
+        (self.type_name)()
+    }
+
+    /// Get the default value of the field
+    pub fn default_value(&self) -> serde_json::Value {
+        // Serializing the config can't fail, it's all basic types
+        let mut defaults = serde_json::to_value(OsrsConfig::default()).unwrap();
+        defaults[self.key].take()
+    }
+}
+
+/// A type that can be used for a config field. This provides a user-friendly
+/// name for the type, which is shown in `osrs config keys`.
+trait ConfigType {
+    fn type_name() -> String;
+}
+
+impl ConfigType for String {
+    fn type_name() -> String {
+        "string".into()
+    }
+}
+
+impl ConfigType for PathBuf {
+    fn type_name() -> String {
+        "path".into()
+    }
+}
+
+impl ConfigType for bool {
+    fn type_name() -> String {
+        "boolean".into()
+    }
+}
+
+impl ConfigType for u64 {
+    fn type_name() -> String {
+        "integer".into()
+    }
+}
+
+impl ConfigType for usize {
+    fn type_name() -> String {
+        "integer".into()
+    }
+}
+
+impl ConfigType for f64 {
+    fn type_name() -> String {
+        "number".into()
+    }
+}
+
+impl ConfigType for AccountType {
+    fn type_name() -> String {
+        "normal | ironman | hardcore_ironman | ultimate_ironman | skiller \
+        | defence"
+            .into()
+    }
+}
+
+impl ConfigType for Profile {
+    fn type_name() -> String {
+        "profile (with default_player and account_type)".into()
+    }
+}
+
+impl ConfigType for Webhook {
+    fn type_name() -> String {
+        "webhook (with url and kind)".into()
+    }
+}
+
+impl ConfigType for HttpMode {
+    fn type_name() -> String {
+        "live | record | replay".into()
+    }
+}
+
+impl ConfigType for TableStyle {
+    fn type_name() -> String {
+        "ascii | unicode | borderless | markdown | discord".into()
+    }
+}
+
+impl<T: ConfigType> ConfigType for Option<T> {
+    fn type_name() -> String {
+        format!("optional {}", T::type_name())
+    }
+}
+
+impl<T: ConfigType> ConfigType for Vec<T> {
+    fn type_name() -> String {
+        format!("list of {}", T::type_name())
+    }
+}
+
+impl<T: ConfigType> ConfigType for BTreeMap<String, T> {
+    fn type_name() -> String {
+        format!("table of {}", T::type_name())
+    }
 }
 
 // Native implementation, which stores the config on the file system
 #[cfg(not(target_family = "wasm"))]
 mod native {
     use super::*;
-    use std::{
-        fs::OpenOptions,
-        path::{Path, PathBuf},
-    };
+    use std::fs::OpenOptions;
 
     impl OsrsConfig {
-        /// Load config data from the pre-defined config file path. Any missing
-        /// values will be populated with defaults.
-        pub fn load() -> anyhow::Result<Self> {
-            let path = Self::path();
-            Self::figment()
-                .merge(Json::file(&path))
-                .extract()
-                .with_context(|| {
-                    format!(
-                        "Error loading config from file `{}`",
-                        path.display()
-                    )
-                })
-        }
-
-        /// Overwrite the current config file with this value
-        pub fn save(&self) -> anyhow::Result<()> {
+        /// Create a Figment with the contents of the config file merged onto
+        /// the default values
+        pub(super) fn stored_figment(
+            path: Option<&Path>,
+        ) -> anyhow::Result<Figment> {
+            Ok(Self::figment().merge(Json::file(Self::path(path))))
+        }
+
+        /// Describe where config is stored, for error messages
+        pub(super) fn location(path: Option<&Path>) -> String {
+            format!("file `{}`", Self::path(path).display())
+        }
+
+        /// Overwrite the config file with this value. If a path is given, it
+        /// will be written there instead of the default location.
+        pub fn save(&self, path: Option<&Path>) -> anyhow::Result<()> {
             fn write_config(
                 path: &Path,
                 new_cfg_value: &OsrsConfig,
@@ -61,14 +595,22 @@ mod native {
                 Ok(())
             }
 
-            let path = Self::path();
+            let path = Self::path(path);
             write_config(&path, self).with_context(|| {
-                format!("Error writing config to file `{}`", path.display())
+                OsrsError::Config(format!(
+                    "Error writing config to file `{}`",
+                    path.display()
+                ))
             })
         }
 
-        /// Get the path to the file where we store configuration
-        fn path() -> PathBuf {
+        /// Get the path to the file where we store configuration. An explicit
+        /// path from the user takes priority over the default location.
+        fn path(path: Option<&Path>) -> PathBuf {
+            if let Some(path) = path {
+                return path.into();
+            }
+
             let config_dir = if cfg!(debug_assertions) {
                 // In dev mode, always use current dir so we don't pollute the
                 // fs
@@ -96,38 +638,285 @@ mod wasm {
     impl OsrsConfig {
         const STORAGE_KEY: &'static str = "config";
 
-        /// Load config data from browser local storage. Any missing values will
-        /// be populated with defaults.
-        pub fn load() -> anyhow::Result<Self> {
+        /// Create a Figment with the config from browser local storage merged
+        /// onto the default values
+        pub(super) fn stored_figment(
+            path: Option<&Path>,
+        ) -> anyhow::Result<Figment> {
+            Self::check_path(path)?;
             let storage = LocalStorage::new()?;
             // If nothing is stored, fall back to empty data
             let stored_data = storage
                 .get(Self::STORAGE_KEY)?
                 .unwrap_or_else(|| "{}".into());
+            Ok(Self::figment().merge(Json::string(&stored_data)))
+        }
 
-            Self::figment()
-                .merge(Json::string(&stored_data))
-                .extract()
-                .with_context(|| {
-                    format!(
-                        "Error loading config from local storage key `{}`. Contents: {}",
-                        Self::STORAGE_KEY,
-                        stored_data
-                    )
-                })
+        /// Describe where config is stored, for error messages
+        pub(super) fn location(_path: Option<&Path>) -> String {
+            format!("local storage key `{}`", Self::STORAGE_KEY)
         }
 
         /// Overwrite the current config value in local storage with this
         /// config.
-        pub fn save(&self) -> anyhow::Result<()> {
+        pub fn save(&self, path: Option<&Path>) -> anyhow::Result<()> {
+            Self::check_path(path)?;
             let storage = LocalStorage::new()?;
             let config = serde_json::to_string(self)?;
             storage.set(Self::STORAGE_KEY, &config).with_context(|| {
-                format!(
+                OsrsError::Config(format!(
                     "Error writing config to local storage key `{}`",
                     Self::STORAGE_KEY
-                )
+                ))
             })
         }
+
+        /// Config always lives in local storage, so custom paths aren't
+        /// supported
+        fn check_path(path: Option<&Path>) -> anyhow::Result<()> {
+            match path {
+                None => Ok(()),
+                Some(_) => Err(OsrsError::UnsupportedEnvironment(
+                    "Custom config paths are not supported in the browser"
+                        .into(),
+                )
+                .into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        // Every field should be documented, and in sync with serialization
+        let defaults = serde_json::to_value(OsrsConfig::default()).unwrap();
+        let keys: Vec<&str> =
+            OsrsConfig::FIELDS.iter().map(|field| field.key).collect();
+        let serialized_keys: Vec<&str> = defaults
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys.len(), serialized_keys.len());
+        for field in OsrsConfig::FIELDS {
+            assert!(serialized_keys.contains(&field.key));
+            assert!(!field.doc().is_empty());
+        }
+
+        let field = OsrsConfig::field("http_fixtures").unwrap();
+        assert_eq!(field.type_name(), "optional path");
+        assert_eq!(
+            field.doc(),
+            "Directory of recorded HTTP responses, for the `record` and \
+            `replay` HTTP modes"
+        );
+        assert_eq!(
+            OsrsConfig::field("wiki_url").unwrap().default_value(),
+            "https://oldschool.runescape.wiki/"
+        );
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        assert_eq!(
+            OsrsConfig::field("default_playr").unwrap_err().to_string(),
+            "Argument error: Unknown config key `default_playr`. Did you mean \
+            `default_player`? Run `osrs config keys` to see all available keys."
+        );
+        assert_eq!(
+            OsrsConfig::field("zzz").unwrap_err().to_string(),
+            "Argument error: Unknown config key `zzz`. \
+            Run `osrs config keys` to see all available keys."
+        );
+    }
+
+    #[test]
+    fn test_with_value() {
+        let config = OsrsConfig::default();
+        let config = config.with_value("http_mode", "replay").unwrap();
+        assert_eq!(config.http_mode, HttpMode::Replay);
+        // Values that look like other types are still strings if that's what
+        // the field needs
+        let config = config.with_value("default_player", "1234").unwrap();
+        assert_eq!(config.default_player.as_deref(), Some("1234"));
+        let config = config.with_value("default_player", "null").unwrap();
+        assert_eq!(config.default_player, None);
+
+        assert!(config.with_value("http_mode", "sometimes").is_err());
+        assert!(config.with_value("not_a_key", "value").is_err());
+        // Can't go inside a field that isn't a table
+        assert!(config.with_value("wiki_url.inner", "value").is_err());
+        assert!(config.with_value("wiki_url.a.b", "value").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        // Names as they're mapped by Env
+        let vars = [
+            ("default_player", "1234"),
+            ("http_mode", "replay"),
+            ("profiles.iron.account_type", "ironman"),
+            ("not_a_key", "ignored"),
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()));
+        let config =
+            OsrsConfig::default().with_env_overrides_from(vars).unwrap();
+        assert_eq!(config.default_player.as_deref(), Some("1234"));
+        assert_eq!(config.http_mode, HttpMode::Replay);
+        assert_eq!(
+            config.profiles["iron"].account_type,
+            Some(AccountType::Ironman)
+        );
+
+        let error = OsrsConfig::default()
+            .with_env_overrides_from([(
+                "http_mode".to_owned(),
+                "sometimes".to_owned(),
+            )])
+            .unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("`OSRS_HTTP_MODE`"),
+            "{:#}",
+            error
+        );
+
+        // Real variables, with a name no other test uses. The prefix matches
+        // regardless of case.
+        std::env::set_var("osrs_PROFILES__ENV_TEST__DEFAULT_PLAYER", "1234");
+        let config = OsrsConfig::default()
+            .with_env_overrides_from(OsrsConfig::env_vars())
+            .unwrap();
+        assert_eq!(
+            config.profiles["env_test"].default_player.as_deref(),
+            Some("1234")
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let config = OsrsConfig::default()
+            .with_value("default_player", "Lynx Titan")
+            .unwrap()
+            .with_value("profiles.iron.default_player", "Iron Lynx")
+            .unwrap()
+            .with_value("profiles.iron.account_type", "ironman")
+            .unwrap()
+            .with_value("profiles.alt.account_type", "defence")
+            .unwrap();
+
+        // No active profile
+        let resolved = config.clone().with_profile(None).unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Lynx Titan"));
+        assert_eq!(resolved.account_type, AccountType::Normal);
+
+        let resolved = config.clone().with_profile(Some("iron")).unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Iron Lynx"));
+        assert_eq!(resolved.account_type, AccountType::Ironman);
+
+        // Values that the profile doesn't set are inherited
+        let config = config.with_value("active_profile", "alt").unwrap();
+        let resolved = config.clone().with_profile(None).unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Lynx Titan"));
+        assert_eq!(resolved.account_type, AccountType::Defence);
+
+        // Active profile doesn't exist anymore
+        let stale = config.clone().with_value("active_profile", "old").unwrap();
+        assert_eq!(stale.clone().with_profile(None).unwrap(), stale);
+
+        assert_eq!(
+            config.with_profile(Some("main")).unwrap_err().to_string(),
+            "Argument error: Unknown profile `main`. \
+            Available profiles: alt, iron"
+        );
+    }
+
+    /// Environment overrides beat the profile, and can pick the profile
+    #[test]
+    fn test_profile_env_overrides() {
+        let config = OsrsConfig::default()
+            .with_value("profiles.main.default_player", "Zezima")
+            .unwrap()
+            .with_value("profiles.iron.account_type", "ironman")
+            .unwrap()
+            .with_value("active_profile", "main")
+            .unwrap();
+        let vars = |vars: &[(&str, &str)]| {
+            vars.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let resolved = config.clone().with_overrides(None, vars(&[])).unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Zezima"));
+
+        let resolved = config
+            .clone()
+            .with_overrides(None, vars(&[("default_player", "Lynx")]))
+            .unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Lynx"));
+        assert_eq!(resolved.active_profile.as_deref(), Some("main"));
+
+        let resolved = config
+            .clone()
+            .with_overrides(Some("iron"), vars(&[("default_player", "Lynx")]))
+            .unwrap();
+        assert_eq!(resolved.default_player.as_deref(), Some("Lynx"));
+        assert_eq!(resolved.account_type, AccountType::Ironman);
+
+        let resolved = config
+            .with_overrides(None, vars(&[("active_profile", "iron")]))
+            .unwrap();
+        assert_eq!(resolved.default_player, None);
+        assert_eq!(resolved.account_type, AccountType::Ironman);
+        assert_eq!(resolved.active_profile.as_deref(), Some("iron"));
+    }
+
+    #[test]
+    fn test_value() {
+        let config = OsrsConfig::default()
+            .with_value("aliases.lynx", "hiscore 'Lynx Titan'")
+            .unwrap();
+        assert_eq!(
+            config.value("http_mode").unwrap(),
+            serde_json::json!("live")
+        );
+        assert_eq!(
+            config.value("aliases").unwrap(),
+            serde_json::json!({"lynx": "hiscore 'Lynx Titan'"})
+        );
+        assert_eq!(
+            config.value("aliases.lynx").unwrap(),
+            serde_json::json!("hiscore 'Lynx Titan'")
+        );
+        // Missing keys and tables within a table are null
+        assert!(config.value("aliases.kc").unwrap().is_null());
+        assert!(config
+            .value("profiles.iron.account_type")
+            .unwrap()
+            .is_null());
+
+        assert!(config.value("not_a_key").is_err());
+        assert!(config.value("not_a_key.inner").is_err());
+        assert!(config.value("wiki_url.inner").is_err());
+        assert!(config.value("default_player.inner").is_err());
+    }
+
+    #[test]
+    fn test_without_value() {
+        let config = OsrsConfig::default()
+            .with_value("wiki_url", "http://localhost")
+            .unwrap()
+            .with_value("default_player", "Lynx Titan")
+            .unwrap();
+        let config = config.without_value("wiki_url").unwrap();
+        assert_eq!(config.wiki_url, OsrsConfig::default().wiki_url);
+        let config = config.without_value("default_player").unwrap();
+        assert_eq!(config, OsrsConfig::default());
+        assert!(config.without_value("not_a_key").is_err());
     }
 }
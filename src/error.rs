@@ -1,6 +1,26 @@
+use serde::Serialize;
 use thiserror::Error;
 
 /// Generic error type for stuff that can go wrong during command execution.
+///
+/// Each variant maps to a process exit code, so scripts can tell different
+/// failures apart:
+///
+/// | Code | Meaning                                              |
+/// | ---- | ---------------------------------------------------- |
+/// | 0    | Success                                              |
+/// | 1    | Unknown/uncategorized error                          |
+/// | 2    | Invalid arguments                                    |
+/// | 3    | Player not found on the hiscores                     |
+/// | 4    | Upstream service unavailable                         |
+/// | 5    | Rate limited by upstream service                     |
+/// | 6    | Unexpected response from upstream service            |
+/// | 7    | Error reading or writing config                      |
+/// | 8    | Data not available offline                           |
+/// | 9    | Not supported in this environment (e.g. the browser) |
+/// | 10   | One or more commands in a script failed              |
+///
+/// If a plugin fails, its own exit code is used instead.
 #[derive(Debug, Error)]
 pub enum OsrsError {
     #[error("Argument error: {0}")]
@@ -12,6 +32,66 @@ pub enum OsrsError {
     #[error("Invalid level. Must be between 1 and 127, got: {0}")]
     InvalidLevel(usize),
 
+    /// A line of input in the shell or a script couldn't be parsed as a
+    /// command
+    #[cfg(feature = "cli")]
+    #[error(transparent)]
+    InvalidCommand(structopt::clap::Error),
+
+    /// The hiscores have no data for a player
+    #[error("Player not found: {0}")]
+    PlayerNotFound(String),
+
+    /// An upstream service couldn't be reached or returned a server error
+    #[error("Upstream service unavailable")]
+    UpstreamUnavailable(#[source] reqwest::Error),
+
+    /// An upstream service didn't respond in time
+    #[error("Request to `{url}` timed out after {}s", timeout.as_secs_f64())]
+    Timeout {
+        url: String,
+        timeout: std::time::Duration,
+    },
+
+    /// An upstream service rejected a request for being over its rate limit
+    #[error("Rate limited by upstream service, try again later")]
+    RateLimited(#[source] reqwest::Error),
+
+    /// An upstream service returned an error status that doesn't fall into
+    /// any other category
+    #[error("Request failed with status {status}")]
+    HttpStatus {
+        status: u16,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// An upstream service returned a response that we couldn't parse
+    #[error("Unexpected response from `{url}`")]
+    InvalidResponse {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// Error loading or saving config. The message includes the location
+    #[error("{0}")]
+    Config(String),
+
+    /// Running in offline mode, and the requested data isn't in the cache
+    #[error("No cached data for `{0}`. Try again without --offline")]
+    NotCached(String),
+
+    /// Some commands in a script failed. Each failure has already been
+    /// reported individually.
+    #[error("{failed} of {total} commands failed")]
+    ScriptFailed { failed: usize, total: usize },
+
+    /// An external plugin command exited with an error. The plugin should
+    /// have already reported the details itself.
+    #[error("Plugin `{name}` failed with exit code {exit_code}")]
+    PluginFailed { name: String, exit_code: i32 },
+
     /// User attempted an action that isn't supported in this environment. This
     /// error *shouldn't* ever happen. Could be something like attempting an
     /// action in the browser that's only supported natively.
@@ -25,3 +105,169 @@ pub enum OsrsError {
     #[error("Unexpected type of JavaScript value, expected String")]
     ExpectedString,
 }
+
+impl OsrsError {
+    /// Exit code for errors that aren't an [OsrsError]
+    pub const UNKNOWN_EXIT_CODE: i32 = 1;
+    /// Exit code for invalid CLI arguments
+    pub const ARGS_EXIT_CODE: i32 = 2;
+
+    /// Get the process exit code for this error. See the type-level docs for
+    /// a full list.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::ArgsError(_)
+            | Self::UnknownSkill(_)
+            | Self::InvalidLevel(_) => Self::ARGS_EXIT_CODE,
+            #[cfg(feature = "cli")]
+            Self::InvalidCommand(_) => Self::ARGS_EXIT_CODE,
+            Self::PlayerNotFound(_) => 3,
+            Self::UpstreamUnavailable(_) | Self::Timeout { .. } => 4,
+            Self::RateLimited(_) => 5,
+            Self::HttpStatus { .. } | Self::InvalidResponse { .. } => 6,
+            Self::Config(_) => 7,
+            Self::NotCached(_) => 8,
+            Self::ScriptFailed { .. } => 10,
+            Self::PluginFailed { exit_code, .. } => *exit_code,
+            #[cfg(target_family = "wasm")]
+            Self::UnsupportedEnvironment(_) => 9,
+            #[cfg(target_family = "wasm")]
+            Self::ExpectedString => Self::UNKNOWN_EXIT_CODE,
+        }
+    }
+
+    /// Get a short, stable identifier for the category of this error, for
+    /// machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ArgsError(_)
+            | Self::UnknownSkill(_)
+            | Self::InvalidLevel(_) => "invalid_args",
+            #[cfg(feature = "cli")]
+            Self::InvalidCommand(_) => "invalid_args",
+            Self::PlayerNotFound(_) => "player_not_found",
+            Self::UpstreamUnavailable(_) | Self::Timeout { .. } => {
+                "upstream_unavailable"
+            }
+            Self::RateLimited(_) => "rate_limited",
+            Self::HttpStatus { .. } | Self::InvalidResponse { .. } => {
+                "invalid_response"
+            }
+            Self::Config(_) => "config",
+            Self::NotCached(_) => "not_cached",
+            Self::ScriptFailed { .. } => "script_failed",
+            Self::PluginFailed { .. } => "plugin_failed",
+            #[cfg(target_family = "wasm")]
+            Self::UnsupportedEnvironment(_) => "unsupported_environment",
+            #[cfg(target_family = "wasm")]
+            Self::ExpectedString => "unknown",
+        }
+    }
+
+    /// Find the [OsrsError] that describes an error, if any. This checks the
+    /// error itself, any context attached to it, and its chain of sources.
+    pub fn find(error: &anyhow::Error) -> Option<&Self> {
+        error
+            .downcast_ref::<Self>()
+            .or_else(|| error.chain().find_map(|e| e.downcast_ref::<Self>()))
+    }
+}
+
+/// A machine-readable description of an error that caused a command to fail
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorReport {
+    /// Short identifier for the category of error, e.g. `player_not_found`
+    pub kind: &'static str,
+    /// Human-readable message, including the chain of causes
+    pub message: String,
+    /// The exit code that the process should exit with
+    pub exit_code: i32,
+}
+
+impl From<&anyhow::Error> for ErrorReport {
+    fn from(error: &anyhow::Error) -> Self {
+        let (kind, exit_code) = match OsrsError::find(error) {
+            Some(osrs_error) => (osrs_error.kind(), osrs_error.exit_code()),
+            None => ("unknown", OsrsError::UNKNOWN_EXIT_CODE),
+        };
+        Self {
+            kind,
+            message: format!("{:#}", error),
+            exit_code,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl From<&structopt::clap::Error> for ErrorReport {
+    fn from(error: &structopt::clap::Error) -> Self {
+        Self {
+            kind: "invalid_args",
+            message: error.message.clone(),
+            exit_code: OsrsError::ARGS_EXIT_CODE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_report() {
+        let report = ErrorReport::from(&anyhow::Error::from(
+            OsrsError::PlayerNotFound("Zezima".into()),
+        ));
+        assert_eq!(report.kind, "player_not_found");
+        assert_eq!(report.message, "Player not found: Zezima");
+        assert_eq!(report.exit_code, 3);
+
+        // Errors should be found even if they're used as context
+        let error: anyhow::Error =
+            Err::<(), _>(std::io::Error::other("disk on fire"))
+                .context(OsrsError::Config("Error loading config".into()))
+                .unwrap_err();
+        let report = ErrorReport::from(&error);
+        assert_eq!(report.kind, "config");
+        assert_eq!(report.message, "Error loading config: disk on fire");
+        assert_eq!(report.exit_code, 7);
+
+        // Other errors are uncategorized
+        let report = ErrorReport::from(&anyhow::anyhow!("oh no"));
+        assert_eq!(report.kind, "unknown");
+        assert_eq!(report.exit_code, 1);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_error_report_clap() {
+        use structopt::StructOpt;
+
+        // Unknown commands are parsed as external, so use an unknown flag
+        let error =
+            crate::OsrsOptions::from_iter_safe(["osrs", "--not-a-flag"])
+                .unwrap_err();
+        let report = ErrorReport::from(&error);
+        assert_eq!(report.kind, "invalid_args");
+        assert!(report.message.contains("--not-a-flag"));
+        assert_eq!(report.exit_code, 2);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_machine_output_requested() {
+        let requested = |line: &str| {
+            let args: Vec<String> = line.split(' ').map(String::from).collect();
+            crate::OsrsOptions::is_machine_output_requested(&args)
+        };
+        assert!(requested("osrs --format json nope"));
+        assert!(requested("osrs hiscore --format=csv"));
+        assert!(!requested("osrs hiscore"));
+        // Invalid args can't be parsed, so the format is found directly
+        assert!(requested("osrs --format json hiscore --not-a-flag"));
+        assert!(requested("osrs --not-a-flag --format=ndjson"));
+        assert!(!requested("osrs --not-a-flag --format text"));
+        assert!(!requested("osrs --not-a-flag -- --format json"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Player data from the OSRS hiscores.
+
+use crate::{config::OsrsConfig, error::OsrsError, utils::http};
+use serde::{Deserialize, Serialize};
+
+pub use crate::utils::skill::Skill;
+
+/// Which set of hiscores a player is ranked on. Each account type has its own
+/// hiscores, which only include players of that type.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    /// The main hiscores, which include every player
+    #[default]
+    Normal,
+    Ironman,
+    HardcoreIronman,
+    UltimateIronman,
+    Skiller,
+    /// One defence pures
+    Defence,
+}
+
+impl AccountType {
+    /// Get the suffix that's added to the hiscore URL for this account type
+    fn url_suffix(self) -> &'static str {
+        match self {
+            Self::Normal => "",
+            Self::Ironman => "_ironman",
+            Self::HardcoreIronman => "_hardcore_ironman",
+            Self::UltimateIronman => "_ultimate",
+            Self::Skiller => "_skiller",
+            Self::Defence => "_skiller_defence",
+        }
+    }
+}
+
+/// One skill for a player in the hiscores.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HiscoreSkill {
+    /// The skill name.
+    pub name: Skill,
+    /// The player's rank in this skill (higher is better). -1 if unranked
+    pub rank: isize,
+    /// The player's level in the skill.
+    pub level: usize,
+    /// The player's total xp in the skill.
+    pub xp: usize,
+}
+
+/// A minigame/boss/other stat tracked on the hiscores. This captures everything
+/// other than skills.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HiscoreActivity {
+    /// The minigame/boss name
+    pub name: String,
+    /// The player's rank in this minigame. -1 if unranked
+    pub rank: isize,
+    /// The minigame score/completion count/kill count. -1 if unranked
+    pub score: isize,
+}
+
+/// Hiscore results for a player.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HiscorePlayer {
+    /// Data on all skills for the player. Missing skills (ones that the
+    /// hiscores didn't provide data on) will be excluded here
+    pub skills: Vec<HiscoreSkill>,
+    /// Data on all minigames/bosses for the player. Missing minigames (ones
+    /// that the hiscores didn't provide data on) will be excluded here
+    pub activities: Vec<HiscoreActivity>,
+}
+
+impl HiscorePlayer {
+    /// Load a player's data from the hiscores for the given account type. The
+    /// API URL is pulled from the config.
+    pub async fn load(
+        cfg: &OsrsConfig,
+        username: &str,
+        account_type: AccountType,
+    ) -> anyhow::Result<Self> {
+        let base_url = format!(
+            "{}{}",
+            cfg.hiscore_url.trim_end_matches('/'),
+            account_type.url_suffix()
+        );
+        let mut data: Self = http::get(
+            &http::join_url(&base_url, "index_lite.json"),
+            &[("player", username)],
+        )
+        .await
+        // The hiscores give a 404 for unknown players
+        .map_err(|error| match OsrsError::find(&error) {
+            Some(OsrsError::HttpStatus { status: 404, .. }) => {
+                OsrsError::PlayerNotFound(username.into()).into()
+            }
+            _ => error,
+        })?;
+
+        // Filter out activities with no history. This matches the behavior of
+        // the official hiscores site
+        data.activities.retain(|activity| activity.rank >= 0);
+
+        Ok(data)
+    }
+
+    /// Load a player's stats from a combination of a command line argument
+    /// and the config. If a name was supplied on the command line, use that,
+    /// otherwise fall back to the config. If there's no username present there
+    /// either, then return an error. The account type from the config only
+    /// applies to the default player, since other players could be any type.
+    ///
+    /// This is useful for many commands that accept a `--player` argument.
+    #[cfg(feature = "cli")]
+    pub(crate) async fn load_from_args(
+        cfg: &OsrsConfig,
+        username_override: &[String],
+    ) -> anyhow::Result<Self> {
+        let (username, account_type) =
+            match (username_override, &cfg.default_player) {
+                // No arg provided, empty default - error
+                (&[], None) => Err(anyhow::Error::from(OsrsError::ArgsError(
+                    "No player given".into(),
+                ))),
+                // No arg provided, but we have a default - use the default
+                (&[], Some(default_player)) => {
+                    Ok((default_player.clone(), cfg.account_type))
+                }
+                // Arg was provided, return that
+                (&[_, ..], _) => {
+                    Ok((username_override.join(" "), AccountType::Normal))
+                }
+            }?;
+        Self::load(cfg, &username, account_type).await
+    }
+
+    /// Get data for a single skill from the player. Return `None` if we have
+    /// no data for that skill. This is rare, but possible.
+    pub fn skill(&self, skill: Skill) -> Option<&HiscoreSkill> {
+        self.skills.iter().find(|s| s.name == skill)
+    }
+}
@@ -0,0 +1,101 @@
+export type AccountType =
+  | "normal"
+  | "ironman"
+  | "hardcore_ironman"
+  | "ultimate_ironman"
+  | "skiller"
+  | "defence";
+
+export interface HiscoreSkill {
+  name: string;
+  /** -1 if unranked */
+  rank: number;
+  level: number;
+  xp: number;
+}
+
+export interface HiscoreActivity {
+  name: string;
+  rank: number;
+  score: number;
+}
+
+export interface HiscorePlayer {
+  skills: HiscoreSkill[];
+  activities: HiscoreActivity[];
+}
+
+export interface Item {
+  id: number;
+  name: string;
+  examine: string;
+  members: boolean;
+  lowalch: number | null;
+  highalch: number | null;
+  limit: number | null;
+  value: number;
+}
+
+export interface ItemPrice {
+  high: number | null;
+  highTime: number | null;
+  low: number | null;
+  lowTime: number | null;
+}
+
+export interface ItemWithPrice {
+  item: Item;
+  /** null if the item hasn't been traded recently */
+  price: ItemPrice | null;
+}
+
+export interface DropOptions {
+  /** Drop rate, as a number or a string like "1/50" or "2%" */
+  probability: number | string;
+  /** Number of chances for the drop, e.g. kill count */
+  iterations: number;
+  /** Rolls on the loot table per iteration. Defaults to 1 */
+  rolls?: number;
+  /** Target number of successes, e.g. "1+" (the default), "3-" or "2" */
+  target?: string;
+}
+
+export interface DropChance {
+  probability: number;
+  iterations: number;
+  rolls: number;
+  target: string;
+  /** Probability (0 to 1) of hitting the target */
+  chance: number;
+}
+
+/** Give exactly one of from_xp/from_level, and to_xp/to_level/plus_xp */
+export interface XpOptions {
+  from_xp?: number;
+  from_level?: number;
+  to_xp?: number;
+  to_level?: number;
+  /** Added to the destination XP, or the source XP if there's no destination */
+  plus_xp?: number;
+}
+
+export interface XpDiff {
+  source_xp: number;
+  source_level: number;
+  dest_xp: number;
+  dest_level: number;
+  /** Negative if the destination is below the source */
+  xp_diff: number;
+}
+
+export interface ErrorReport {
+  kind: string;
+  message: string;
+  exit_code: number;
+}
+
+/** Look up a player on the hiscores. The account type defaults to normal. */
+export function getHiscore(
+  username: string,
+  account_type?: AccountType
+): Promise<HiscorePlayer>;
@@ -0,0 +1,532 @@
+//! Typed JavaScript API. Unlike `runCommand`, these functions return plain JS
+//! objects rather than rendered text, so the website can build its own UI on
+//! top of the data. TypeScript definitions for the returned objects are
+//! included in the generated bindings. Errors are thrown as objects with the
+//! fields `kind`, `message` and `exit_code`, same as `runCommandStreaming`.
+//!
+//! Field names match the CLI's JSON output.
+//!
+//! The bindings themselves only exist in wasm, but the options they take are
+//! handled natively too, so they can be tested.
+
+use crate::{
+    calc::{
+        drop::{parse_probability, DropChance, TargetRange},
+        xp::{level_to_xp, XpDiff},
+    },
+    error::OsrsError,
+};
+use serde::Deserialize;
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// TypeScript definitions for everything that's passed to or returned from
+/// the bindings. Tests check these against the Rust types, so they can't
+/// drift apart.
+#[cfg_attr(target_family = "wasm", wasm_bindgen(typescript_custom_section))]
+const TYPES: &str = include_str!("js.d.ts");
+
+/// Options for `calcDrop`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+struct DropOptions {
+    probability: Probability,
+    iterations: usize,
+    rolls: Option<f64>,
+    target: Option<String>,
+}
+
+impl DropOptions {
+    /// Validate the options and calculate the drop chance
+    fn calculate(self) -> anyhow::Result<DropChance> {
+        let probability = match self.probability {
+            Probability::Number(probability) => probability,
+            Probability::Text(probability) => parse_probability(&probability)?,
+        };
+        let target = match self.target {
+            Some(target) => target.parse()?,
+            None => TargetRange::Gte(1),
+        };
+        Ok(DropChance::calculate(
+            probability,
+            self.iterations,
+            self.rolls.unwrap_or(1.0),
+            target,
+        )?)
+    }
+}
+
+/// A probability can be given as a number, or any string that the CLI accepts
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(untagged)]
+enum Probability {
+    Number(f64),
+    Text(String),
+}
+
+/// Options for `calcXp`
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+struct XpOptions {
+    from_xp: Option<usize>,
+    from_level: Option<usize>,
+    to_xp: Option<usize>,
+    to_level: Option<usize>,
+    plus_xp: Option<usize>,
+}
+
+impl XpOptions {
+    /// Validate the options and calculate the XP difference
+    fn calculate(self) -> anyhow::Result<XpDiff> {
+        let source_xp = match (self.from_xp, self.from_level) {
+            (Some(xp), None) => xp,
+            (None, Some(level)) => level_to_xp(level)?,
+            _ => {
+                return Err(OsrsError::ArgsError(
+                    "Must specify exactly one of from_xp or from_level".into(),
+                )
+                .into())
+            }
+        };
+        let dest_xp = match (self.to_xp, self.to_level) {
+            (Some(xp), None) => xp,
+            (None, Some(level)) => level_to_xp(level)?,
+            // A relative offset with no absolute dest is based on the source
+            (None, None) if self.plus_xp.is_some() => source_xp,
+            _ => {
+                return Err(OsrsError::ArgsError(
+                    "Must specify exactly one of to_xp or to_level".into(),
+                )
+                .into())
+            }
+        };
+        // Numbers from JS can be much bigger than a 32-bit usize
+        let dest_xp = dest_xp
+            .checked_add(self.plus_xp.unwrap_or_default())
+            .ok_or_else(|| {
+                OsrsError::ArgsError(format!(
+                    "Destination XP is too large, the maximum is {}",
+                    usize::MAX
+                ))
+            })?;
+        Ok(XpDiff::new(source_xp, dest_xp))
+    }
+}
+
+/// The functions exported to JS
+#[cfg(target_family = "wasm")]
+mod bindings {
+    use super::*;
+    use crate::{
+        error::ErrorReport,
+        hiscore::{AccountType, HiscorePlayer},
+        prices,
+        utils::browser::{report_to_js, to_js},
+        OsrsConfig,
+    };
+
+    /// Look up a player on the hiscores. The account type defaults to normal.
+    // The generated signature can't express an optional parameter with a
+    // custom type, so it's declared in the TypeScript definitions instead
+    #[wasm_bindgen(js_name = getHiscore, skip_typescript)]
+    pub async fn get_hiscore(
+        username: String,
+        account_type: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        async fn helper(
+            username: String,
+            account_type: Option<String>,
+        ) -> anyhow::Result<JsValue> {
+            let account_type: AccountType = match account_type {
+                Some(account_type) => from_js(account_type.into())?,
+                None => AccountType::default(),
+            };
+            let config = load_config()?;
+            let player =
+                HiscorePlayer::load(&config, &username, account_type).await?;
+            to_js(&player)
+        }
+
+        helper(username, account_type).await.map_err(error_to_js)
+    }
+
+    /// Search items by name, with current Grand Exchange prices
+    #[wasm_bindgen(
+        js_name = searchPrices,
+        unchecked_return_type = "ItemWithPrice[]"
+    )]
+    pub async fn search_prices(query: String) -> Result<JsValue, JsValue> {
+        async fn helper(query: String) -> anyhow::Result<JsValue> {
+            let config = load_config()?;
+            to_js(&prices::search_prices(&config, &query).await?)
+        }
+
+        helper(query).await.map_err(error_to_js)
+    }
+
+    /// Calculate the probability of getting a drop
+    #[wasm_bindgen(js_name = calcDrop, unchecked_return_type = "DropChance")]
+    pub fn calc_drop(
+        #[wasm_bindgen(unchecked_param_type = "DropOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        fn helper(options: JsValue) -> anyhow::Result<JsValue> {
+            to_js(&from_js::<DropOptions>(options)?.calculate()?)
+        }
+
+        helper(options).map_err(error_to_js)
+    }
+
+    /// Calculate the XP needed to get from one XP total/level to another
+    #[wasm_bindgen(js_name = calcXp, unchecked_return_type = "XpDiff")]
+    pub fn calc_xp(
+        #[wasm_bindgen(unchecked_param_type = "XpOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        fn helper(options: JsValue) -> anyhow::Result<JsValue> {
+            to_js(&from_js::<XpOptions>(options)?.calculate()?)
+        }
+
+        helper(options).map_err(error_to_js)
+    }
+
+    /// Load config from browser storage, and apply its network settings
+    fn load_config() -> anyhow::Result<OsrsConfig> {
+        let config = OsrsConfig::load(None, None)?;
+        crate::configure(&config)?;
+        Ok(config)
+    }
+
+    /// Convert an argument from JS into its Rust counterpart
+    fn from_js<T: for<'de> Deserialize<'de>>(
+        value: JsValue,
+    ) -> anyhow::Result<T> {
+        serde_wasm_bindgen::from_value(value).map_err(|error| {
+            // The message comes from a JS error, which has a redundant prefix
+            let message = error.to_string();
+            let message = message.trim_start_matches("Error: ");
+            OsrsError::ArgsError(format!("Invalid argument: {}", message))
+                .into()
+        })
+    }
+
+    fn error_to_js(error: anyhow::Error) -> JsValue {
+        report_to_js(ErrorReport::from(&error))
+    }
+}
+
+// The custom section replaces the constant in wasm, so the definitions can
+// only be checked natively
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::{
+        calc::drop::TargetRange,
+        error::ErrorReport,
+        hiscore::{AccountType, HiscoreActivity, HiscorePlayer, HiscoreSkill},
+        prices::{Item, ItemPrice, ItemWithPrice},
+        utils::skill::Skill,
+    };
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    /// A field in a TypeScript interface
+    #[derive(Debug)]
+    struct TsField {
+        optional: bool,
+        type_name: String,
+    }
+
+    /// Fields of each TypeScript interface, by name
+    type TsInterfaces = BTreeMap<&'static str, BTreeMap<&'static str, TsField>>;
+
+    /// Parse the fields of every interface in the TypeScript definitions
+    fn ts_interfaces() -> TsInterfaces {
+        let mut interfaces = BTreeMap::new();
+        let mut current: Option<(&str, BTreeMap<&str, TsField>)> = None;
+        for line in TYPES.lines().map(str::trim) {
+            if let Some(name) = line
+                .strip_prefix("export interface ")
+                .and_then(|rest| rest.strip_suffix(" {"))
+            {
+                current = Some((name, BTreeMap::new()));
+            } else if line == "}" {
+                if let Some((name, fields)) = current.take() {
+                    interfaces.insert(name, fields);
+                }
+            } else if let Some((_, fields)) = &mut current {
+                if line.starts_with("/**") {
+                    continue;
+                }
+                let (name, type_name) = line.split_once(": ").unwrap();
+                let (name, optional) = match name.strip_suffix('?') {
+                    Some(name) => (name, true),
+                    None => (name, false),
+                };
+                fields.insert(
+                    name,
+                    TsField {
+                        optional,
+                        type_name: type_name.trim_end_matches(';').into(),
+                    },
+                );
+            }
+        }
+        interfaces
+    }
+
+    /// Check that a value serializes to an object with the same fields as a
+    /// TypeScript interface. Options in the value should be `None`, so that we
+    /// can check they're nullable in TypeScript.
+    fn assert_output_matches(interface: &str, value: &impl Serialize) {
+        let interfaces = ts_interfaces();
+        let ts_fields = interfaces
+            .get(interface)
+            .unwrap_or_else(|| panic!("No interface {}", interface));
+        let value = serde_json::to_value(value).unwrap();
+        let fields = value.as_object().unwrap();
+        assert_eq!(
+            fields.keys().map(String::as_str).collect::<Vec<_>>(),
+            ts_fields.keys().copied().collect::<Vec<_>>(),
+            "Fields of {}",
+            interface
+        );
+        for (name, value) in fields {
+            let ts_field = &ts_fields[name.as_str()];
+            assert!(!ts_field.optional, "{}.{} is optional", interface, name);
+            assert_eq!(
+                value.is_null(),
+                ts_field.type_name.ends_with(" | null"),
+                "Nullability of {}.{}",
+                interface,
+                name
+            );
+        }
+    }
+
+    /// Check that the fields of an options type match a TypeScript interface,
+    /// and that exactly the optional fields can be left out
+    fn assert_input_matches<T: Serialize + for<'de> Deserialize<'de>>(
+        interface: &str,
+        value: &T,
+    ) {
+        let interfaces = ts_interfaces();
+        let ts_fields = &interfaces[interface];
+        let value = serde_json::to_value(value).unwrap();
+        let fields = value.as_object().unwrap();
+        assert_eq!(
+            fields.keys().map(String::as_str).collect::<Vec<_>>(),
+            ts_fields.keys().copied().collect::<Vec<_>>(),
+            "Fields of {}",
+            interface
+        );
+        for (name, ts_field) in ts_fields {
+            let mut without_field = fields.clone();
+            without_field.remove(*name);
+            assert_eq!(
+                serde_json::from_value::<T>(without_field.into()).is_ok(),
+                ts_field.optional,
+                "Optionality of {}.{}",
+                interface,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_types_account_type() {
+        // The error for an unknown variant lists all the valid ones
+        let error = serde_json::from_value::<AccountType>(json!("zzz"))
+            .unwrap_err()
+            .to_string();
+        let (_, expected) = error.split_once("expected one of ").unwrap();
+        let variants: Vec<String> = expected
+            .split(", ")
+            .map(|variant| format!("| \"{}\"", variant.trim_matches('`')))
+            .collect();
+        let (_, ts_type) =
+            TYPES.split_once("export type AccountType =").unwrap();
+        let ts_variants: Vec<String> = ts_type
+            .lines()
+            .map(str::trim)
+            .skip(1)
+            .take_while(|line| line.starts_with('|'))
+            .map(|line| line.trim_end_matches(';').to_owned())
+            .collect();
+        assert_eq!(ts_variants, variants);
+    }
+
+    #[test]
+    fn test_types_output() {
+        let skill = HiscoreSkill {
+            name: Skill::Attack,
+            rank: 1,
+            level: 99,
+            xp: 13_034_431,
+        };
+        let activity = HiscoreActivity {
+            name: "Vorkath".into(),
+            rank: 1,
+            score: 1,
+        };
+        assert_output_matches("HiscoreSkill", &skill);
+        assert_output_matches("HiscoreActivity", &activity);
+        assert_output_matches(
+            "HiscorePlayer",
+            &HiscorePlayer {
+                skills: vec![skill],
+                activities: vec![activity],
+            },
+        );
+
+        let item = Item {
+            id: 385,
+            name: "Shark".into(),
+            examine: "I'd better be careful eating this.".into(),
+            members: true,
+            low_alch: None,
+            high_alch: None,
+            limit: None,
+            value: 300,
+        };
+        assert_output_matches("Item", &item);
+        assert_output_matches(
+            "ItemPrice",
+            &ItemPrice {
+                high: None,
+                high_time: None,
+                low: None,
+                low_time: None,
+            },
+        );
+        assert_output_matches(
+            "ItemWithPrice",
+            &ItemWithPrice { item, price: None },
+        );
+
+        assert_output_matches(
+            "DropChance",
+            &DropChance::calculate(0.5, 2, 1.0, TargetRange::Gte(1)).unwrap(),
+        );
+        assert_output_matches("XpDiff", &XpDiff::new(0, 83));
+        assert_output_matches(
+            "ErrorReport",
+            &ErrorReport::from(&anyhow::anyhow!("oh no")),
+        );
+    }
+
+    #[test]
+    fn test_types_input() {
+        assert_input_matches(
+            "DropOptions",
+            &DropOptions {
+                probability: Probability::Text("1/50".into()),
+                iterations: 50,
+                rolls: Some(1.0),
+                target: Some("1+".into()),
+            },
+        );
+        assert_input_matches(
+            "XpOptions",
+            &XpOptions {
+                from_xp: Some(0),
+                from_level: Some(1),
+                to_xp: Some(83),
+                to_level: Some(2),
+                plus_xp: Some(10),
+            },
+        );
+    }
+
+    fn drop_options(options: Value) -> anyhow::Result<DropChance> {
+        serde_json::from_value::<DropOptions>(options)?.calculate()
+    }
+
+    fn xp_options(options: Value) -> anyhow::Result<XpDiff> {
+        serde_json::from_value::<XpOptions>(options)?.calculate()
+    }
+
+    #[test]
+    fn test_calc_drop() {
+        // Probability can be a number or a string, with defaults for the rest
+        let chance =
+            drop_options(json!({"probability": 0.5, "iterations": 2})).unwrap();
+        assert_eq!(
+            chance,
+            DropChance::calculate(0.5, 2, 1.0, TargetRange::Gte(1)).unwrap()
+        );
+        let chance = drop_options(json!({
+            "probability": "50%",
+            "iterations": 2,
+            "rolls": 2.0,
+            "target": "2"
+        }))
+        .unwrap();
+        assert_eq!(
+            chance,
+            DropChance::calculate(0.5, 2, 2.0, TargetRange::Eq(2)).unwrap()
+        );
+
+        assert!(
+            drop_options(json!({"probability": 1.5, "iterations": 2})).is_err()
+        );
+        assert!(
+            drop_options(json!({"probability": "lots", "iterations": 2}))
+                .is_err()
+        );
+        assert!(drop_options(json!({
+            "probability": 0.5,
+            "iterations": 2,
+            "target": "most"
+        }))
+        .is_err());
+        // Typos aren't silently ignored
+        assert!(
+            drop_options(json!({"probability": 0.5, "iterationz": 2})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_calc_xp() {
+        assert_eq!(
+            xp_options(json!({"from_level": 1, "to_level": 2})).unwrap(),
+            XpDiff::new(0, 83)
+        );
+        assert_eq!(
+            xp_options(json!({"from_xp": 100, "to_xp": 50})).unwrap(),
+            XpDiff::new(100, 50)
+        );
+        // Relative to the destination, or the source if there's no destination
+        assert_eq!(
+            xp_options(json!({"from_xp": 0, "to_level": 2, "plus_xp": 10}))
+                .unwrap(),
+            XpDiff::new(0, 93)
+        );
+        assert_eq!(
+            xp_options(json!({"from_xp": 50, "plus_xp": 10})).unwrap(),
+            XpDiff::new(50, 60)
+        );
+
+        let error = |options| xp_options(options).unwrap_err().to_string();
+        assert_eq!(
+            error(json!({"to_xp": 50})),
+            "Argument error: Must specify exactly one of from_xp or from_level"
+        );
+        assert_eq!(
+            error(json!({"from_xp": 0, "from_level": 1, "to_xp": 50})),
+            "Argument error: Must specify exactly one of from_xp or from_level"
+        );
+        assert_eq!(
+            error(json!({"from_xp": 0})),
+            "Argument error: Must specify exactly one of to_xp or to_level"
+        );
+        assert!(xp_options(json!({"from_level": 0, "to_xp": 50})).is_err());
+        // Overflow is an error rather than a panic
+        assert!(error(
+            json!({"from_xp": 0, "to_xp": usize::MAX, "plus_xp": 1})
+        )
+        .contains("too large"));
+    }
+}
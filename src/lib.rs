@@ -1,53 +1,153 @@
+//! Old School RuneScape utilities, as a CLI and as a library.
+//!
+//! The library API is split into modules by data source:
+//!
+//! - [hiscore]: player stats from the hiscores
+//! - [prices]: item data and Grand Exchange prices
+//! - [calc]: calculators that don't need any external data, and the
+//!   probability math they use ([calc::math])
+//!
+//! Functions that load data take an [OsrsConfig], which defines which APIs
+//! to use. Responses are cached the same way as in the CLI.
+//!
+//! ```no_run
+//! use osrs_cli::{hiscore::{AccountType, HiscorePlayer, Skill}, OsrsConfig};
+//!
+//! async fn slayer_level() -> anyhow::Result<Option<usize>> {
+//!     let config = OsrsConfig::load(None, None)?;
+//!     osrs_cli::configure(&config)?;
+//!     let player =
+//!         HiscorePlayer::load(&config, "Lynx Titan", AccountType::Normal)
+//!             .await?;
+//!     Ok(player.skill(Skill::Slayer).map(|skill| skill.level))
+//! }
+//! ```
+//!
+//! The command line interface is enabled by the default `cli` feature. To use
+//! just the library, disable default features.
+
+pub mod calc;
+#[cfg(feature = "cli")]
 mod commands;
 mod config;
 mod error;
+pub mod hiscore;
+#[cfg(any(target_family = "wasm", test))]
+mod js;
+pub mod prices;
 mod utils;
 
+#[cfg(all(feature = "cli", not(target_family = "wasm")))]
+use crate::utils::plugin;
+use crate::utils::{
+    http::{self, HttpSettings},
+    transport::{self, Transport},
+};
+#[cfg(feature = "cli")]
 use crate::{
     commands::{
-        CalcCommand, Command, CommandType, ConfigCommand, HiscoreCommand,
-        PingCommand, PriceCommand, WikiCommand,
+        CacheCommand, CalcCommand, Command, CommandType, CompleteItemCommand,
+        CompletionsCommand, ConfigCommand, ExporterCommand, HiscoreCommand,
+        PingCommand, PriceCommand, RunCommand, ServeCommand, ShellCommand,
+        WikiCommand,
     },
-    utils::context::CommandContext,
+    utils::{
+        alias,
+        context::{self, CommandContext, GlobalOptions},
+        notify::{Tee, Webhook},
+        output::OutputFormat,
+    },
+};
+#[cfg(feature = "cli")]
+use std::{collections::BTreeMap, io::Write};
+#[cfg(feature = "cli")]
+use structopt::{
+    clap::{AppSettings, Arg},
+    StructOpt,
 };
-use std::io::Write;
-use structopt::StructOpt;
+
+pub use crate::{
+    config::{OsrsConfig, Profile},
+    error::{ErrorReport, OsrsError},
+    utils::transport::HttpMode,
+};
+
+/// Apply the network settings from a config (HTTP mode, timeouts, retries,
+/// etc.) to all subsequent requests. The CLI does this for every command.
+/// Library users should call it after loading a config, otherwise the
+/// defaults are used.
+pub fn configure(config: &OsrsConfig) -> anyhow::Result<()> {
+    transport::set_transport(Transport::from_config(config)?);
+    http::set_http_settings(HttpSettings::from_config(config));
+    Ok(())
+}
 
 /// All top-level CLI commands.
+#[cfg(feature = "cli")]
 #[derive(Debug, StructOpt)]
 enum OsrsCommandType {
+    Cache(CacheCommand),
     Calc(CalcCommand),
+    // Used by shell completion scripts, users shouldn't need to see it
+    #[structopt(setting = AppSettings::Hidden)]
+    CompleteItem(CompleteItemCommand),
+    Completions(CompletionsCommand),
     #[structopt(visible_alias = "cfg")]
     Config(ConfigCommand),
+    Exporter(ExporterCommand),
     #[structopt(visible_alias = "hs")]
     Hiscore(HiscoreCommand),
     Ping(PingCommand),
     #[structopt(visible_alias = "ge")]
     Price(PriceCommand),
+    Run(RunCommand),
+    Serve(ServeCommand),
+    Shell(ShellCommand),
     Wiki(WikiCommand),
+    /// Any command that isn't built in. These are expanded from aliases
+    /// before execution.
+    #[structopt(external_subcommand)]
+    External(Vec<String>),
 }
 
+#[cfg(feature = "cli")]
 impl<O: Write> CommandType<O> for OsrsCommandType {
     fn command(&self) -> &dyn Command<O> {
         match &self {
+            Self::Cache(cmd) => cmd,
             Self::Calc(cmd) => cmd,
+            Self::CompleteItem(cmd) => cmd,
+            Self::Completions(cmd) => cmd,
             Self::Config(cmd) => cmd,
+            Self::Exporter(cmd) => cmd,
             Self::Hiscore(cmd) => cmd,
             Self::Ping(cmd) => cmd,
             Self::Price(cmd) => cmd,
+            Self::Run(cmd) => cmd,
+            Self::Serve(cmd) => cmd,
+            Self::Shell(cmd) => cmd,
             Self::Wiki(cmd) => cmd,
+            Self::External(cmd) => cmd,
         }
     }
 }
 
 /// Oldschool RuneScape CLI.
 /// Bugs/suggestions: https://github.com/LucasPickering/osrs-cli/issues
+#[cfg(feature = "cli")]
 #[derive(Debug, StructOpt)]
 pub struct OsrsOptions {
+    #[structopt(flatten)]
+    global: GlobalOptions,
     #[structopt(subcommand)]
     cmd: OsrsCommandType,
+    /// Config that was loaded to expand aliases, so the command doesn't have
+    /// to load it again
+    #[structopt(skip)]
+    config: Option<OsrsConfig>,
 }
 
+#[cfg(feature = "cli")]
 impl OsrsOptions {
     /// Execute the command defined by this options object. This is the main
     /// entrypoint to the program. Callers can customize how the these options
@@ -56,17 +156,351 @@ impl OsrsOptions {
     /// The type `O` defines how command output will be written. For native
     /// environments, this will be stdout, whereas for the browser it will be
     /// a string buffer (which presumably gets written to the DOM).
-    pub async fn run<O: Write>(self, output: O) -> anyhow::Result<()> {
-        let context = CommandContext::load(output)?;
-        self.cmd.command().execute(context).await
+    pub async fn run<O: Write>(mut self, output: O) -> anyhow::Result<()> {
+        let config = self.config.take();
+        self.run_with_config(config.as_ref(), output).await
     }
+
+    /// Execute the command like [Self::run], with a config that's already
+    /// loaded, e.g. by the shell. If `None`, config is loaded from storage.
+    pub(crate) async fn run_with_config<O: Write>(
+        self,
+        config: Option<&OsrsConfig>,
+        output: O,
+    ) -> anyhow::Result<()> {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(interval) = self.global.watch {
+            return self.watch(config, output, interval).await;
+        }
+        #[cfg(target_family = "wasm")]
+        if self.global.watch.is_some() {
+            return Err(OsrsError::UnsupportedEnvironment(
+                "Watch mode isn't supported in the browser".into(),
+            )
+            .into());
+        }
+
+        if self.global.notify.is_none() {
+            self.execute(config, output).await?;
+            return Ok(());
+        }
+        // Keep a copy of the output to send once the command is done
+        let mut output = Tee::new(output);
+        if let Some(webhook) = self.execute(config, &mut output).await? {
+            webhook.send(&output.copy()).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute the command once. If a notification target was given, its
+    /// webhook is returned so the caller can send the output. The target is
+    /// checked before the command runs, so a typo doesn't waste a run.
+    async fn execute<O: Write>(
+        &self,
+        config: Option<&OsrsConfig>,
+        output: O,
+    ) -> anyhow::Result<Option<Webhook>> {
+        let context = match config {
+            Some(config) => CommandContext::with_config(
+                output,
+                &self.global,
+                config.clone(),
+            )?,
+            None => CommandContext::load(output, &self.global)?,
+        };
+        let webhook = self
+            .global
+            .notify
+            .as_deref()
+            .map(|target| Webhook::find(context.config(), target).cloned())
+            .transpose()?;
+        self.cmd.command().execute(context).await?;
+        Ok(webhook)
+    }
+
+    /// Execute the command over and over, redrawing its output each time.
+    /// Errors from the first run are returned, since they're probably a
+    /// mistake in the command. After that, errors are printed and the command
+    /// keeps running, so a blip in the network doesn't end the watch.
+    #[cfg(not(target_family = "wasm"))]
+    async fn watch<O: Write>(
+        mut self,
+        config: Option<&OsrsConfig>,
+        mut output: O,
+        interval: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        use crate::utils::{http, terminal, time, watch};
+        use colored::Colorize;
+
+        if matches!(
+            self.cmd,
+            OsrsCommandType::Exporter(_)
+                | OsrsCommandType::Serve(_)
+                | OsrsCommandType::Shell(_)
+        ) {
+            return Err(OsrsError::ArgsError(
+                "Can't watch a command that never finishes".into(),
+            )
+            .into());
+        }
+
+        // Machine-readable output is appended instead, so each run can be
+        // parsed separately
+        let redraw = !self.global.format.is_machine();
+        let clear = redraw && terminal::is_terminal();
+        let mut previous: Option<String> = None;
+        let mut run = 1;
+        loop {
+            // Otherwise data loaded in the first run would be reused forever,
+            // from memory or the persistent cache
+            self.global = self.global.for_watch_run(run);
+            http::clear_memory_caches();
+            let mut buffer = Vec::new();
+            let result = self.execute(config, &mut buffer).await;
+            let current = String::from_utf8_lossy(&buffer).into_owned();
+
+            if redraw {
+                watch::draw_header(&mut output, interval, run, clear)?;
+            }
+            match previous.as_deref().filter(|_| redraw) {
+                Some(previous) => {
+                    let highlighted =
+                        watch::highlight_changes(previous, &current, |word| {
+                            word.reversed().to_string()
+                        });
+                    output.write_all(highlighted.as_bytes())?;
+                }
+                None => output.write_all(current.as_bytes())?,
+            }
+            output.flush()?;
+
+            match result {
+                Ok(webhook) => {
+                    // Only notify about changes, e.g. a level up
+                    let changed = previous.as_deref() != Some(&current);
+                    if let Some(webhook) = webhook.filter(|_| changed) {
+                        if let Err(error) = webhook.send(&current).await {
+                            eprintln!("{:#}", error);
+                        }
+                    }
+                    previous = Some(current);
+                }
+                Err(error) if run == 1 => return Err(error),
+                Err(error) => eprintln!("{:#}", error),
+            }
+            time::sleep(interval).await;
+            run += 1;
+        }
+    }
+
+    /// Execute the command, writing output to stdout. If stdout is a
+    /// terminal, text output that doesn't fit on the screen is sent through
+    /// a pager, unless disabled with `--no-pager`.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn run_stdout(self) -> anyhow::Result<()> {
+        use crate::utils::pager::Pager;
+
+        // Interactive commands and ones that write straight to the terminal
+        // can't be paged
+        let pageable = !self.global.no_pager
+            && !self.global.format.is_machine()
+            && self.global.watch.is_none()
+            && !matches!(
+                self.cmd,
+                OsrsCommandType::Exporter(_)
+                    | OsrsCommandType::Ping(_)
+                    | OsrsCommandType::Serve(_)
+                    | OsrsCommandType::Shell(_)
+            );
+        match Pager::new().filter(|_| pageable) {
+            Some(mut pager) => {
+                let result = self.run(&mut pager).await;
+                // Show all output before any error gets printed
+                pager.finish()?;
+                result
+            }
+            None => self.run(std::io::stdout()).await,
+        }
+    }
+
+    /// Parse options from a list of args, the first being the binary name.
+    /// If the command is a user-defined alias from the config, it's expanded
+    /// before parsing. Aliases are also listed in the help output.
+    pub fn from_iter_with_aliases<I>(args: I) -> Result<Self, OsrsError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<String> = args.into_iter().collect();
+        // If the args are invalid, they'll fail again below
+        let global = Self::parse_global(&args).unwrap_or_default();
+        // Errors are ignored here, since they'll be reported when the command
+        // loads the config. Config commands need to work with a broken
+        // config, so it can be fixed.
+        let config = context::load_config(&global).ok();
+        let aliases = config
+            .as_ref()
+            .map(|config| config.aliases.clone())
+            .unwrap_or_default();
+        let mut options = Self::from_iter_with_alias_map(args, &aliases)?;
+        // An alias can pick a different config file or profile
+        if options.global.config == global.config
+            && options.global.profile == global.profile
+        {
+            options.config = config;
+        }
+        Ok(options)
+    }
+
+    /// Parse just the global options, to find the config that aliases are
+    /// loaded from. Aliases are external commands to clap, so they parse
+    /// fine. Help is turned off, so that the help output can list aliases from
+    /// the right config. Returns `None` if the args are invalid.
+    fn parse_global(args: &[String]) -> Option<GlobalOptions> {
+        let matches = Self::clap()
+            .unset_setting(AppSettings::SubcommandRequiredElseHelp)
+            .global_setting(AppSettings::DisableHelpSubcommand)
+            .global_setting(AppSettings::DisableVersion)
+            .arg(Arg::with_name("help").short("h").long("help").global(true))
+            .get_matches_from_safe(args)
+            .ok()?;
+        Some(GlobalOptions::from_clap(&matches))
+    }
+
+    /// Parse options from a list of args, with aliases that have already been
+    /// loaded, e.g. by a shell. Otherwise the same as
+    /// [Self::from_iter_with_aliases].
+    pub fn from_iter_with_alias_map<I>(
+        args: I,
+        aliases: &BTreeMap<String, String>,
+    ) -> Result<Self, OsrsError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<String> = args.into_iter().collect();
+        let alias_help = alias::help(aliases);
+        let parse = |args: &[String]| {
+            let mut app = Self::clap();
+            if let Some(alias_help) = &alias_help {
+                app = app.after_help(alias_help.as_str());
+            }
+            app.get_matches_from_safe(args)
+                .map(|matches| Self::from_clap(&matches))
+                .map_err(OsrsError::InvalidCommand)
+        };
+
+        let options = parse(&args)?;
+        // Config commands still work with invalid aliases, so they can be
+        // fixed
+        if !matches!(options.cmd, OsrsCommandType::Config(_)) {
+            alias::validate(aliases)?;
+        }
+        let OsrsCommandType::External(external_args) = &options.cmd else {
+            return Ok(options);
+        };
+        // The first arg is the command name, which is never empty
+        let (name, alias_args) = external_args.split_first().unwrap();
+        let Some(alias) = aliases.get(name) else {
+            return if is_plugin(name) {
+                Ok(options)
+            } else {
+                Err(unknown_command(name, aliases))
+            };
+        };
+        let expanded: Vec<String> = args[..1]
+            .iter()
+            .cloned()
+            .chain(alias::expand(name, alias, alias_args)?)
+            .collect();
+        // Global options given before the alias apply too. Aliases can't
+        // refer to other aliases, so we don't expand again. They can refer to
+        // plugins though.
+        let mut expanded_options = parse(&expanded)?;
+        if let OsrsCommandType::External(args) = &expanded_options.cmd {
+            if !is_plugin(&args[0]) {
+                return Err(unknown_command(&args[0], aliases));
+            }
+        }
+        expanded_options.global =
+            expanded_options.global.with_defaults(&options.global);
+        Ok(expanded_options)
+    }
+
+    /// Did the user request a machine-readable output format? If so, errors
+    /// should be reported in a machine-readable format too.
+    pub fn is_machine_output(&self) -> bool {
+        self.global.format.is_machine()
+    }
+
+    /// Do the args request a machine-readable output format? This is the same
+    /// as [Self::is_machine_output], for reporting errors from parsing the
+    /// args. If they're too invalid to parse, `--format` is looked for
+    /// directly.
+    pub fn is_machine_output_requested(args: &[String]) -> bool {
+        if let Some(global) = Self::parse_global(args) {
+            return global.format.is_machine();
+        }
+        let mut format = OutputFormat::default();
+        let mut args = args.iter().skip(1).take_while(|arg| *arg != "--");
+        while let Some(arg) = args.next() {
+            let value = match arg.strip_prefix("--format") {
+                Some("") => args.next().map(String::as_str),
+                Some(value) => value.strip_prefix('='),
+                None => continue,
+            };
+            if let Some(value) = value.and_then(|value| value.parse().ok()) {
+                format = value;
+            }
+        }
+        format.is_machine()
+    }
+}
+
+/// Is there a plugin for a command that isn't built in? Plugins are only
+/// supported natively.
+#[cfg(all(feature = "cli", not(target_family = "wasm")))]
+fn is_plugin(name: &str) -> bool {
+    plugin::find(name).is_some()
+}
+
+#[cfg(all(feature = "cli", target_family = "wasm"))]
+fn is_plugin(_name: &str) -> bool {
+    false
+}
+
+/// Error for a command that isn't built in, an alias or a plugin. If there's
+/// a command or alias with a similar name, it's suggested.
+#[cfg(feature = "cli")]
+fn unknown_command(
+    name: &str,
+    aliases: &BTreeMap<String, String>,
+) -> OsrsError {
+    let mut message = format!("Unknown command {:?}.", name);
+    let suggestion = alias::builtin_names()
+        .into_iter()
+        .chain(aliases.keys().cloned())
+        .map(|command| (strsim::jaro_winkler(name, &command), command))
+        .filter(|(similarity, _)| *similarity > 0.8)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b));
+    if let Some((_, suggested_command)) = suggestion {
+        message.push_str(&format!(" Did you mean `{}`?", suggested_command));
+    }
+    message.push_str(&format!(
+        " Run `osrs --help` to see all commands and aliases, or add a plugin \
+        named `osrs-{}` to your PATH",
+        name
+    ));
+    OsrsError::ArgsError(message)
 }
 
 /// Public WebAssembly API
-#[cfg(target_family = "wasm")]
+#[cfg(all(target_family = "wasm", feature = "cli"))]
 mod wasm {
     use super::*;
-    use crate::error::OsrsError;
+    use crate::utils::{
+        browser::{report_to_js, CallbackWriter},
+        terminal,
+    };
+    use std::io::Write;
     use wasm_bindgen::prelude::*;
 
     /// Initialization. This function gets called when the wasm module is loaded
@@ -79,34 +513,87 @@ mod wasm {
     /// of arguments, where each one is a string. You *must* pass the binary
     /// name as the first argument.
     ///
-    /// We can't take in Vec<String> because wasm_bindgen.
-    /// Clean up after https://github.com/rustwasm/wasm-bindgen/issues/168
+    /// All output is buffered and returned once the command finishes. Errors
+    /// are formatted into the output. Use `runCommandStreaming` to get output
+    /// as it's generated and errors as structured objects.
+    ///
+    /// `width` is the width of the terminal in columns, if known. Tables are
+    /// wrapped to fit it.
     #[wasm_bindgen(js_name = runCommand)]
-    pub async fn run_command(command: Vec<JsValue>) -> String {
+    pub async fn run_command(
+        command: Vec<JsValue>,
+        width: Option<u16>,
+    ) -> String {
         // Replace this with a try block after is stable
         // https://github.com/rust-lang/rust/issues/31436
         async fn helper(command: Vec<JsValue>) -> anyhow::Result<String> {
-            // Convert each arg to a string
-            let args: Vec<String> = command
-                .into_iter()
-                .map::<Result<String, OsrsError>, _>(|value| {
-                    value.as_string().ok_or(OsrsError::ExpectedString)
-                })
-                // Pull all results into one
-                .collect::<Result<_, _>>()?;
-
             // Write all output to a buffer, which we'll return to JS
-            // TODO figure out how to stream output back to JS
             let mut output = Vec::new();
-            let options = OsrsOptions::from_iter_safe(args)?;
+            let options =
+                OsrsOptions::from_iter_with_aliases(parse_args(command)?)?;
             options.run(&mut output).await?;
             Ok(String::from_utf8(output)?)
         }
 
+        terminal::set_width(width);
         match helper(command).await {
             Ok(output) => output,
-            // TODO return error here instead and make the caller print it
             Err(err) => format!("{}\n", err),
         }
     }
+
+    /// Wasm entrypoint that streams output back to JS as it's generated.
+    /// Command should be a list of string arguments, including the binary name
+    /// as the first argument, same as `runCommand`. `onOutput` is called with
+    /// each chunk of output, as a string.
+    ///
+    /// The returned promise resolves once the command is done. If the command
+    /// fails, it rejects with an object describing the error, with the fields
+    /// `kind`, `message` and `exit_code`. This matches the error report that
+    /// the CLI prints for machine-readable output formats. `width` is the
+    /// same as for `runCommand`.
+    #[wasm_bindgen(js_name = runCommandStreaming)]
+    pub async fn run_command_streaming(
+        command: Vec<JsValue>,
+        on_output: js_sys::Function,
+        width: Option<u16>,
+    ) -> Result<(), JsValue> {
+        terminal::set_width(width);
+        let mut output = CallbackWriter::new(on_output);
+        let args = parse_args(command)
+            .map_err(|err| report_to_js(ErrorReport::from(&err)))?;
+        let options = match OsrsOptions::from_iter_with_aliases(args) {
+            Ok(options) => options,
+            // --help and --version are reported as "errors", but they're
+            // really just output
+            Err(OsrsError::InvalidCommand(err)) if !err.use_stderr() => {
+                return writeln!(output, "{}", err.message).map_err(|err| {
+                    report_to_js(ErrorReport::from(&anyhow::Error::from(err)))
+                });
+            }
+            Err(err) => {
+                return Err(report_to_js(ErrorReport::from(
+                    &anyhow::Error::from(err),
+                )))
+            }
+        };
+        options
+            .run(output)
+            .await
+            .map_err(|err| report_to_js(ErrorReport::from(&err)))
+    }
+
+    /// Convert a list of JS values to strings. We can't take in Vec<String>
+    /// because wasm_bindgen.
+    /// Clean up after https://github.com/rustwasm/wasm-bindgen/issues/168
+    fn parse_args(command: Vec<JsValue>) -> anyhow::Result<Vec<String>> {
+        let args = command
+            .into_iter()
+            .map::<Result<String, OsrsError>, _>(|value| {
+                value.as_string().ok_or(OsrsError::ExpectedString)
+            })
+            // Pull all results into one
+            .collect::<Result<_, _>>()?;
+        Ok(args)
+    }
 }
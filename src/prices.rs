@@ -1,24 +1,58 @@
-//! This module contains utilities related to items. This includes functions
-//! as well as a list of item IDs that we statically reference for certain
-//! utilities within the codebase.
+//! Item data and Grand Exchange prices, from the [OSRS Wiki's Real-time
+//! Prices API](https://oldschool.runescape.wiki/w/RuneScape:Real-time_Prices).
 
-use crate::utils::http::HttpCache;
+use crate::{
+    config::OsrsConfig,
+    utils::http::{self, CacheGuard, HttpCache},
+};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 lazy_static! {
     /// We share a single client here for the whole program so that it can
     /// persist caches for the data it loads.
-    ///
-    /// We could hypothetically put this in the command context, but I didn't
-    /// feel like it because then you have to plumb it everywhere.
-    pub static ref WIKI_ITEM_CLIENT: WikiItemClient = WikiItemClient::new();
+    static ref WIKI_ITEM_CLIENT: WikiItemClient = WikiItemClient::new();
+}
+
+/// Search items by name. This will do a caseless substring match, and return
+/// all items that match. The API URL is pulled from the config.
+pub async fn search(
+    config: &OsrsConfig,
+    query: &str,
+) -> anyhow::Result<Vec<Item>> {
+    WIKI_ITEM_CLIENT.search(config, query).await
+}
+
+/// Search items by name, with current price data for each one. This uses the
+/// same search criteria as [search].
+pub async fn search_prices(
+    config: &OsrsConfig,
+    query: &str,
+) -> anyhow::Result<Vec<ItemWithPrice>> {
+    WIKI_ITEM_CLIENT.search_prices(config, query).await
+}
+
+/// Get items by ID, with current price data for each one. IDs that don't match
+/// any item are skipped.
+pub async fn prices_by_id(
+    config: &OsrsConfig,
+    ids: &[usize],
+) -> anyhow::Result<Vec<ItemWithPrice>> {
+    WIKI_ITEM_CLIENT.prices_by_id(config, ids).await
+}
+
+/// Fetch the latest prices, even if they've already been loaded. Otherwise,
+/// prices are only loaded once per process, so long-running processes should
+/// call this periodically to keep them up to date.
+pub async fn refresh_prices(config: &OsrsConfig) -> anyhow::Result<()> {
+    WIKI_ITEM_CLIENT.refresh_prices(config).await
 }
 
 /// A client for fetching item and item price data from the [OSRS Wiki's
 /// Real-time Prices API](https://oldschool.runescape.wiki/w/RuneScape:Real-time_Prices).
-pub struct WikiItemClient {
+/// Responses are cached, both in memory and persistently.
+struct WikiItemClient {
     item_mapping: HttpCache<Vec<Item>>,
     prices: HttpCache<ItemPriceResponse>,
 }
@@ -26,19 +60,24 @@ pub struct WikiItemClient {
 impl WikiItemClient {
     fn new() -> Self {
         Self {
+            // Static item data only changes with game updates
             item_mapping: HttpCache::new(
-                "https://prices.runescape.wiki/api/v1/osrs/mapping".into(),
+                "item_mapping",
+                Duration::from_secs(24 * 60 * 60),
             ),
             prices: HttpCache::new(
-                "https://prices.runescape.wiki/api/v1/osrs/latest".into(),
+                "latest_prices",
+                Duration::from_secs(5 * 60),
             ),
         }
     }
 
-    /// Search items by name. This will do a caseless substring match, and
-    /// return all items that match.
-    pub async fn search(&self, query: &str) -> anyhow::Result<Vec<Item>> {
-        let items = self.item_mapping.load().await?;
+    async fn search(
+        &self,
+        config: &OsrsConfig,
+        query: &str,
+    ) -> anyhow::Result<Vec<Item>> {
+        let items = self.load_items(config).await?;
 
         // We want caseless search, so convert everything to lowercase
         // If this turns out to be really slow we could use a regex instead
@@ -52,15 +91,52 @@ impl WikiItemClient {
             .collect())
     }
 
-    /// Search items by name with price data. This uses the same search criteria
-    /// as [Self::search].
-    pub async fn search_prices(
+    async fn search_prices(
         &self,
+        config: &OsrsConfig,
         query: &str,
     ) -> anyhow::Result<Vec<ItemWithPrice>> {
-        let items = self.search(query).await?;
-        let item_prices = &self.prices.load().await?.data;
-        // Join price data in for each item
+        let items = self.search(config, query).await?;
+        self.with_prices(config, items).await
+    }
+
+    async fn prices_by_id(
+        &self,
+        config: &OsrsConfig,
+        ids: &[usize],
+    ) -> anyhow::Result<Vec<ItemWithPrice>> {
+        let all_items = self.load_items(config).await?;
+        let items = ids
+            .iter()
+            .filter_map(|id| all_items.iter().find(|item| item.id == *id))
+            .cloned()
+            .collect();
+        // Release the item data before loading prices
+        drop(all_items);
+        self.with_prices(config, items).await
+    }
+
+    async fn refresh_prices(&self, config: &OsrsConfig) -> anyhow::Result<()> {
+        self.prices.refresh(&Self::prices_url(config)).await
+    }
+
+    async fn load_items(
+        &self,
+        config: &OsrsConfig,
+    ) -> anyhow::Result<CacheGuard<'_, Vec<Item>>> {
+        self.item_mapping
+            .load(&http::join_url(&config.prices_url, "mapping"))
+            .await
+    }
+
+    /// Join price data in for each item
+    async fn with_prices(
+        &self,
+        config: &OsrsConfig,
+        items: Vec<Item>,
+    ) -> anyhow::Result<Vec<ItemWithPrice>> {
+        let item_prices =
+            &self.prices.load(&Self::prices_url(config)).await?.data;
         let items_with_prices = items
             .into_iter()
             .map(|item| {
@@ -70,20 +146,29 @@ impl WikiItemClient {
             .collect();
         Ok(items_with_prices)
     }
+
+    fn prices_url(config: &OsrsConfig) -> String {
+        http::join_url(&config.prices_url, "latest")
+    }
 }
 
 /// An in-game item. This doesn't include price data, just static data.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Item {
+    /// In-game item ID
     pub id: usize,
     pub name: String,
+    /// Examine text
     pub examine: String,
+    /// Is this a members-only item?
     pub members: bool,
     #[serde(rename = "lowalch")]
     pub low_alch: Option<usize>,
     #[serde(rename = "highalch")]
     pub high_alch: Option<usize>,
+    /// Grand Exchange buy limit, per 4 hours
     pub limit: Option<usize>,
+    /// Store value, which alch values are derived from
     pub value: usize,
 }
 
@@ -91,9 +176,13 @@ pub struct Item {
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemPrice {
+    /// Most recent instant-buy price
     pub high: Option<usize>,
+    /// Unix timestamp (in seconds) of the most recent instant-buy
     pub high_time: Option<usize>,
+    /// Most recent instant-sell price
     pub low: Option<usize>,
+    /// Unix timestamp (in seconds) of the most recent instant-sell
     pub low_time: Option<usize>,
 }
 
@@ -110,7 +199,7 @@ impl ItemPrice {
 
 /// An item's core data paired with its current price data. Price data will be
 /// `None` if it hasn't been traded recently.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ItemWithPrice {
     pub item: Item,
     pub price: Option<ItemPrice>,
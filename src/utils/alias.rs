@@ -0,0 +1,321 @@
+//! User-defined command aliases. Aliases are defined in the config, and
+//! expanded into a full list of args before parsing.
+
+use crate::{error::OsrsError, OsrsCommandType, OsrsOptions};
+use std::{collections::BTreeMap, iter};
+use structopt::{clap::AppSettings, StructOpt};
+
+/// Placeholder for all args that weren't used by a numbered placeholder
+const REST_PLACEHOLDER: &str = "$@";
+
+/// Check if a name is used by a built-in command (or one of its aliases). User
+/// aliases can't use these names.
+pub fn is_builtin(name: &str) -> bool {
+    // Built-in commands take precedence over external ones, so if the name
+    // parses as anything else, it's built in. Adding --help makes sure that
+    // required args don't cause an error.
+    !matches!(
+        OsrsOptions::from_iter_safe(["osrs", name, "--help"]),
+        Ok(OsrsOptions {
+            cmd: OsrsCommandType::External(_),
+            ..
+        })
+    )
+}
+
+/// Get the names of all visible built-in commands, including their aliases
+pub fn builtin_names() -> Vec<String> {
+    // clap 2 has no accessor for subcommands, so use its internal parser. It's
+    // public for generated code, and clap 2 won't change anymore.
+    OsrsOptions::clap()
+        .p
+        .subcommands
+        .iter()
+        .filter(|command| !command.p.is_set(AppSettings::Hidden))
+        .flat_map(|command| {
+            let meta = &command.p.meta;
+            let aliases = meta
+                .aliases
+                .iter()
+                .flatten()
+                .filter(|(_, visible)| *visible)
+                .map(|(alias, _)| alias.to_string());
+            iter::once(meta.name.clone()).chain(aliases)
+        })
+        .collect()
+}
+
+/// Check that an alias name doesn't shadow a built-in command
+pub fn validate_name(name: &str) -> Result<(), OsrsError> {
+    if is_builtin(name) {
+        Err(OsrsError::ArgsError(format!(
+            "Alias `{}` would shadow a built-in command",
+            name
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that none of the given aliases shadow a built-in command. `osrs
+/// config set` won't add one, but it can still come from editing the config
+/// file or an environment override. Such an alias would never be used, so it's
+/// probably a mistake.
+pub fn validate(aliases: &BTreeMap<String, String>) -> Result<(), OsrsError> {
+    for name in aliases.keys() {
+        if is_builtin(name) {
+            return Err(OsrsError::Config(format!(
+                "Alias `{}` would shadow a built-in command. Rename it, or \
+                remove it with `osrs config unset aliases.{}`",
+                name, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Expand an alias into a list of args. `$1`, `$2`, etc. in the alias are
+/// replaced with the corresponding arg, and `$@` with all args that weren't
+/// used by a numbered placeholder. If there's no `$@`, those args are appended
+/// to the end. Placeholders must be whole words.
+pub fn expand(
+    name: &str,
+    alias: &str,
+    args: &[String],
+) -> Result<Vec<String>, OsrsError> {
+    let words = shell_words::split(alias).map_err(|err| {
+        OsrsError::ArgsError(format!("Invalid alias `{}`: {}", name, err))
+    })?;
+
+    // Figure out which args are used by numbered placeholders, so we know
+    // which ones are left over for $@
+    let mut used = vec![false; args.len()];
+    for word in &words {
+        if let Some(index) = placeholder_index(word) {
+            match used.get_mut(index) {
+                Some(used) => *used = true,
+                None => {
+                    return Err(OsrsError::ArgsError(format!(
+                        "Alias `{}` needs at least {} argument(s), got {}",
+                        name,
+                        index + 1,
+                        args.len()
+                    )))
+                }
+            }
+        }
+    }
+    let rest = args
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(arg, _)| arg.clone());
+
+    let mut expanded = Vec::with_capacity(words.len() + args.len());
+    let mut has_rest = false;
+    for word in words {
+        if word == REST_PLACEHOLDER {
+            expanded.extend(rest.clone());
+            has_rest = true;
+        } else if let Some(index) = placeholder_index(&word) {
+            expanded.push(args[index].clone());
+        } else {
+            expanded.push(word);
+        }
+    }
+    if !has_rest {
+        expanded.extend(rest);
+    }
+    Ok(expanded)
+}
+
+/// Generate a help section listing all aliases. Returns `None` if there are
+/// no aliases.
+pub fn help(aliases: &BTreeMap<String, String>) -> Option<String> {
+    let width = aliases.keys().map(|name| name.chars().count()).max()?;
+    let lines: Vec<String> = aliases
+        .iter()
+        .map(|(name, alias)| format!("    {:width$}    {}", name, alias))
+        .collect();
+    Some(format!("ALIASES:\n{}", lines.join("\n")))
+}
+
+/// Get the 0-based arg index for a numbered placeholder like `$1`. Returns
+/// `None` if the word isn't a numbered placeholder.
+fn placeholder_index(word: &str) -> Option<usize> {
+    let number: usize = word.strip_prefix('$')?.parse().ok()?;
+    number.checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_builtin() {
+        assert!(is_builtin("price"));
+        assert!(is_builtin("ge"));
+        assert!(is_builtin("calc"));
+        assert!(is_builtin("help"));
+        assert!(!is_builtin("kc"));
+        assert!(validate_name("hs").is_err());
+        assert!(validate_name("kc").is_ok());
+    }
+
+    #[test]
+    fn test_builtin_names() {
+        let names = builtin_names();
+        for name in ["price", "ge", "hiscore", "hs", "config", "cfg", "shell"] {
+            assert!(names.iter().any(|n| n == name), "{} in {:?}", name, names);
+        }
+        // Hidden commands aren't suggested
+        assert!(!names.iter().any(|n| n == "complete-item"), "{:?}", names);
+    }
+
+    #[test]
+    fn test_validate() {
+        let aliases = [("kc".to_owned(), "calc drop".to_owned())].into();
+        assert!(validate(&aliases).is_ok());
+        let aliases = [("hs".to_owned(), "hiscore 'Lynx Titan'".to_owned())];
+        assert_eq!(
+            validate(&aliases.into()).unwrap_err().to_string(),
+            "Alias `hs` would shadow a built-in command. Rename it, or \
+            remove it with `osrs config unset aliases.hs`"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_aliases() {
+        let parse = |line: &str, aliases: &BTreeMap<String, String>| {
+            OsrsOptions::from_iter_with_alias_map(
+                iter::once("osrs").chain(line.split(' ')).map(String::from),
+                aliases,
+            )
+        };
+        let aliases: BTreeMap<String, String> =
+            [("lynx".to_owned(), "hiscore 'Lynx Titan'".to_owned())].into();
+
+        // Global options before the alias apply to the expansion
+        let options = parse("--offline lynx", &aliases).unwrap();
+        assert!(format!("{:?}", options.cmd).contains("[\"Lynx Titan\"]"));
+        assert!(options.global.offline);
+
+        // Typos of commands and aliases get a suggestion
+        let error = parse("pirce shark", &aliases).unwrap_err().to_string();
+        assert!(error.contains("Did you mean `price`?"), "{}", error);
+        let error = parse("lynz", &aliases).unwrap_err().to_string();
+        assert!(error.contains("Did you mean `lynx`?"), "{}", error);
+        let error = parse("zzz", &aliases).unwrap_err().to_string();
+        assert!(!error.contains("Did you mean"), "{}", error);
+
+        // Aliases that shadow built-ins are reported, except by config
+        // commands so they can be fixed
+        let mut aliases = aliases;
+        aliases.insert("price".into(), "hiscore".into());
+        assert!(matches!(
+            parse("hiscore", &aliases),
+            Err(OsrsError::Config(_))
+        ));
+        assert!(parse("config unset aliases.price", &aliases).is_ok());
+    }
+
+    /// Aliases come from the config file given in the args, which is then
+    /// reused to run the command
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn test_parse_with_config_aliases() {
+        let path = std::env::temp_dir()
+            .join(format!("osrs-alias-config-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"aliases": {"lynx": "hiscore 'Lynx'"}}"#)
+            .unwrap();
+        let path_arg = path.to_str().unwrap();
+        let parse = |line: &str| {
+            OsrsOptions::from_iter_with_aliases(
+                iter::once("osrs").chain(line.split(' ')).map(String::from),
+            )
+        };
+
+        let options = parse(&format!("--config {} lynx", path_arg)).unwrap();
+        assert!(format!("{:?}", options.cmd).contains("[\"Lynx\"]"));
+        let config = options.config.unwrap();
+        assert_eq!(config.aliases["lynx"], "hiscore 'Lynx'");
+
+        // Args after `--` aren't options
+        let options =
+            parse(&format!("--config={} lynx -- --config=x", path_arg))
+                .unwrap();
+        assert!(options.config.is_some());
+
+        // Help lists aliases from the same config
+        let error = parse(&format!("--config {} --help", path_arg))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("lynx    hiscore 'Lynx'"), "{}", error);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_expand() {
+        // No placeholders - args are appended
+        assert_eq!(
+            expand("lynx", "hiscore 'Lynx Titan'", &args(&["--format", "csv"]))
+                .unwrap(),
+            args(&["hiscore", "Lynx Titan", "--format", "csv"])
+        );
+
+        // Numbered placeholders, with leftovers appended
+        assert_eq!(
+            expand(
+                "kc",
+                "calc drop -p $1 --kc $2",
+                &args(&["1/5000", "300", "-t", "2+"])
+            )
+            .unwrap(),
+            args(&["calc", "drop", "-p", "1/5000", "--kc", "300", "-t", "2+"])
+        );
+
+        // Placeholders can be reused and out of order, and $@ goes anywhere
+        assert_eq!(
+            expand("x", "a $2 $@ $2 b", &args(&["1", "2", "3", "4"])).unwrap(),
+            args(&["a", "2", "1", "3", "4", "2", "b"])
+        );
+
+        // Anything else with a $ is left alone
+        assert_eq!(
+            expand("x", "a $0 $b", &[]).unwrap(),
+            args(&["a", "$0", "$b"])
+        );
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        assert_eq!(
+            expand("kc", "calc drop -p $1 --kc $2", &args(&["1/5000"]))
+                .unwrap_err()
+                .to_string(),
+            "Argument error: Alias `kc` needs at least 2 argument(s), got 1"
+        );
+        assert!(expand("x", "hiscore $1", &[]).is_err());
+        assert!(expand("x", "hiscore 'Lynx", &[]).is_err());
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(help(&BTreeMap::new()), None);
+        let aliases = [
+            ("kc".to_owned(), "calc drop -p $1 --kc $2".to_owned()),
+            ("lynx".to_owned(), "hiscore 'Lynx Titan'".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            help(&aliases).unwrap(),
+            "ALIASES:\n    kc      calc drop -p $1 --kc $2\n    \
+            lynx    hiscore 'Lynx Titan'"
+        );
+    }
+}
@@ -1,9 +1,15 @@
 //! Utilities for running in the browser. Only inclued for Wasm targets.
 
-use crate::error::OsrsError;
+use crate::error::{ErrorReport, OsrsError};
 use js_sys::Object;
-use wasm_bindgen::JsCast;
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Storage, Window};
+#[cfg(feature = "cli")]
+use {
+    js_sys::Function,
+    std::io::{self, Write},
+};
 
 /// Access the `Window` object from JS
 pub fn window() -> anyhow::Result<Window> {
@@ -24,6 +30,22 @@ pub fn js_to_anyhow(error: wasm_bindgen::JsValue) -> anyhow::Error {
     anyhow::anyhow!(message)
 }
 
+/// Convert a value to a plain JS object. `None` becomes `null` and maps become
+/// objects, so the result has the same shape as the CLI's JSON output.
+pub fn to_js<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        // The JS error isn't thread-safe, so it can't go in an anyhow error
+        .map_err(|error| anyhow::anyhow!(error.to_string()))
+}
+
+/// Convert an error report to a plain JS object
+pub fn report_to_js(report: ErrorReport) -> JsValue {
+    // Serializing a plain struct can't fail, but if it does somehow, at
+    // least JS gets the message
+    to_js(&report).unwrap_or_else(|_| JsValue::from_str(&report.message))
+}
+
 /// A wrapper around browser local storage
 pub struct LocalStorage {
     storage: Storage,
@@ -54,4 +76,89 @@ impl LocalStorage {
     pub fn set(&self, key: &str, value: &str) -> anyhow::Result<()> {
         self.storage.set(key, value).map_err(js_to_anyhow)
     }
+
+    /// Remove a value from local storage. No-op if the key isn't present
+    #[cfg(feature = "cli")]
+    pub fn remove(&self, key: &str) -> anyhow::Result<()> {
+        self.storage.remove_item(key).map_err(js_to_anyhow)
+    }
+
+    /// Get all keys currently in local storage
+    #[cfg(feature = "cli")]
+    pub fn keys(&self) -> anyhow::Result<Vec<String>> {
+        let length = self.storage.length().map_err(js_to_anyhow)?;
+        let mut keys = Vec::new();
+        for i in 0..length {
+            if let Some(key) = self.storage.key(i).map_err(js_to_anyhow)? {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// An output stream that passes everything written to it to a JS callback, as
+/// a string. This lets JS display output as it's generated, rather than
+/// waiting for the whole command to finish.
+#[cfg(feature = "cli")]
+pub struct CallbackWriter {
+    callback: Function,
+    /// Bytes from the end of a previous write that don't form a complete
+    /// UTF-8 character yet. A write can split a multi-byte character, and we
+    /// can only pass whole characters to JS.
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "cli")]
+impl CallbackWriter {
+    pub fn new(callback: Function) -> Self {
+        Self {
+            callback,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Pass a chunk of text to the callback. Empty chunks are skipped.
+    fn emit(&self, chunk: &str) -> io::Result<()> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        self.callback
+            .call1(&JsValue::NULL, &JsValue::from_str(chunk))
+            .map(|_| ())
+            .map_err(|error| io::Error::other(js_to_anyhow(error).to_string()))
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        // Emit everything up to the last complete character, and hang onto
+        // the rest until the next write
+        let valid_len = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // An incomplete character at the end will be finished by a later
+            // write. Anything else is actually invalid, so let it through
+            // lossily rather than holding onto it forever.
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let chunk: Vec<u8> = self.pending.drain(..valid_len).collect();
+        self.emit(&String::from_utf8_lossy(&chunk))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let chunk = std::mem::take(&mut self.pending);
+        self.emit(&String::from_utf8_lossy(&chunk))
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Drop for CallbackWriter {
+    fn drop(&mut self) {
+        // Nothing we can do with an error here
+        let _ = self.flush();
+    }
 }
@@ -0,0 +1,280 @@
+//! Persistent storage for HTTP responses. This lets us re-use large responses
+//! (e.g. the item mapping) across separate invocations of the program. Each
+//! cached response is stored under a unique key, along with the time it was
+//! fetched, so the caller can decide whether it's still fresh.
+
+use crate::utils::time::now_secs;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// One cached HTTP response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// When the response was fetched, in seconds since the Unix epoch
+    pub fetched_at: u64,
+    /// The raw response body
+    pub body: String,
+}
+
+impl CacheEntry {
+    /// Create a new entry for a response that was just fetched
+    pub fn new(body: String) -> Self {
+        Self {
+            fetched_at: now_secs(),
+            body,
+        }
+    }
+
+    /// How long ago was this entry fetched?
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now_secs().saturating_sub(self.fetched_at))
+    }
+
+    /// Is this entry younger than the given time-to-live?
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        self.age() < ttl
+    }
+}
+
+/// A key-value store for cached responses. The backing storage depends on the
+/// platform (file system for native, local storage for the browser).
+pub struct PersistentCache;
+
+impl PersistentCache {
+    /// Get all entries in the cache, sorted by key
+    #[cfg(feature = "cli")]
+    pub fn list() -> anyhow::Result<Vec<(String, CacheEntry)>> {
+        let mut entries = Vec::new();
+        for key in Self::keys()? {
+            if let Some(entry) = Self::get(&key)? {
+                entries.push((key, entry));
+            }
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(entries)
+    }
+}
+
+// Native implementation, which stores one file per entry in the OS cache dir
+#[cfg(not(target_family = "wasm"))]
+mod native {
+    use super::*;
+    use anyhow::Context;
+    use std::{
+        fs,
+        io::ErrorKind,
+        path::{Path, PathBuf},
+    };
+
+    #[cfg(test)]
+    thread_local! {
+        /// Cache directory for the test running on this thread. This is per
+        /// thread rather than global because tests run in parallel.
+        static TEST_DIR: std::cell::RefCell<Option<PathBuf>> =
+            const { std::cell::RefCell::new(None) };
+    }
+
+    impl PersistentCache {
+        /// Store entries in a temporary directory for the rest of the test
+        /// running on this thread, so tests don't touch the real cache or
+        /// each other's entries. The test should delete the returned
+        /// directory when it's done.
+        #[cfg(test)]
+        pub fn use_test_dir(test_name: &str) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!(
+                "osrs-cache-{}-{}",
+                test_name,
+                std::process::id()
+            ));
+            TEST_DIR
+                .with(|test_dir| *test_dir.borrow_mut() = Some(dir.clone()));
+            dir
+        }
+
+        /// Get a cached entry by key. Returns `None` if there is no entry.
+        pub fn get(key: &str) -> anyhow::Result<Option<CacheEntry>> {
+            let path = Self::entry_path(key);
+            match fs::read_to_string(&path) {
+                Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err).with_context(|| {
+                    format!("Error reading cache file `{}`", path.display())
+                }),
+            }
+        }
+
+        /// Insert or replace an entry in the cache
+        pub fn set(key: &str, entry: &CacheEntry) -> anyhow::Result<()> {
+            let path = Self::entry_path(key);
+            fs::create_dir_all(Self::dir())
+                .and_then(|()| fs::write(&path, serde_json::to_string(entry)?))
+                .with_context(|| {
+                    format!("Error writing cache file `{}`", path.display())
+                })
+        }
+
+        /// Delete all entries in the cache. Returns the number of entries
+        /// deleted.
+        #[cfg(feature = "cli")]
+        pub fn clear() -> anyhow::Result<usize> {
+            let keys = Self::keys()?;
+            for key in &keys {
+                Self::remove(key)?;
+            }
+            Ok(keys.len())
+        }
+
+        /// Delete a single entry from the cache
+        #[cfg(feature = "cli")]
+        pub fn remove(key: &str) -> anyhow::Result<()> {
+            let path = Self::entry_path(key);
+            fs::remove_file(&path).with_context(|| {
+                format!("Error deleting cache file `{}`", path.display())
+            })
+        }
+
+        /// Get the keys of all entries in the cache
+        #[cfg(feature = "cli")]
+        pub(super) fn keys() -> anyhow::Result<Vec<String>> {
+            let dir = Self::dir();
+            let read_dir = match fs::read_dir(&dir) {
+                Ok(read_dir) => read_dir,
+                // Nothing has been cached yet
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    return Ok(Vec::new())
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Error reading cache dir `{}`", dir.display())
+                    })
+                }
+            };
+
+            let mut keys = Vec::new();
+            for dir_entry in read_dir {
+                let path = dir_entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) == Some("json")
+                {
+                    if let Some(key) = path.file_stem().and_then(|s| s.to_str())
+                    {
+                        keys.push(key.to_owned());
+                    }
+                }
+            }
+            Ok(keys)
+        }
+
+        /// Get the path to the file for a single cache entry
+        fn entry_path(key: &str) -> PathBuf {
+            Self::dir().join(format!("{}.json", key))
+        }
+
+        /// Get the directory where we store cached responses
+        fn dir() -> PathBuf {
+            #[cfg(test)]
+            if let Some(dir) = TEST_DIR.with(|dir| dir.borrow().clone()) {
+                return dir;
+            }
+            if cfg!(debug_assertions) {
+                // In dev mode, always use current dir so we don't pollute the
+                // fs
+                Path::new("cache").into()
+            } else {
+                // Use the OS-defined cache directory, if possible. If not
+                // available, fall back to the home dir, then finally current
+                // dir
+                dirs::cache_dir()
+                    .or_else(dirs::home_dir)
+                    .unwrap_or_default()
+                    .join("osrs-cli")
+            }
+        }
+    }
+}
+
+// Wasm implementation, which stores entries in browser local storage
+#[cfg(target_family = "wasm")]
+mod wasm {
+    use super::*;
+    use crate::utils::browser::LocalStorage;
+    use anyhow::Context;
+
+    impl PersistentCache {
+        /// Prefix for all local storage keys, to separate cache entries from
+        /// other stored data
+        const KEY_PREFIX: &'static str = "cache:";
+
+        /// Get a cached entry by key. Returns `None` if there is no entry.
+        pub fn get(key: &str) -> anyhow::Result<Option<CacheEntry>> {
+            let storage = LocalStorage::new()?;
+            match storage.get(&Self::storage_key(key))? {
+                Some(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+                None => Ok(None),
+            }
+        }
+
+        /// Insert or replace an entry in the cache
+        pub fn set(key: &str, entry: &CacheEntry) -> anyhow::Result<()> {
+            let storage = LocalStorage::new()?;
+            let storage_key = Self::storage_key(key);
+            storage
+                .set(&storage_key, &serde_json::to_string(entry)?)
+                .with_context(|| {
+                    format!(
+                        "Error writing cache to local storage key `{}`",
+                        storage_key
+                    )
+                })
+        }
+
+        /// Delete all entries in the cache. Returns the number of entries
+        /// deleted.
+        #[cfg(feature = "cli")]
+        pub fn clear() -> anyhow::Result<usize> {
+            let keys = Self::keys()?;
+            for key in &keys {
+                Self::remove(key)?;
+            }
+            Ok(keys.len())
+        }
+
+        /// Delete a single entry from the cache
+        #[cfg(feature = "cli")]
+        pub fn remove(key: &str) -> anyhow::Result<()> {
+            LocalStorage::new()?.remove(&Self::storage_key(key))
+        }
+
+        /// Get the keys of all entries in the cache
+        #[cfg(feature = "cli")]
+        pub(super) fn keys() -> anyhow::Result<Vec<String>> {
+            Ok(LocalStorage::new()?
+                .keys()?
+                .into_iter()
+                .filter_map(|storage_key| {
+                    storage_key.strip_prefix(Self::KEY_PREFIX).map(String::from)
+                })
+                .collect())
+        }
+
+        fn storage_key(key: &str) -> String {
+            format!("{}{}", Self::KEY_PREFIX, key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_freshness() {
+        let mut entry = CacheEntry::new("{}".into());
+        assert!(entry.is_fresh(Duration::from_secs(60)));
+
+        // Backdate the entry so it's 2 minutes old
+        entry.fetched_at -= 120;
+        assert!(entry.age() >= Duration::from_secs(120));
+        assert!(!entry.is_fresh(Duration::from_secs(60)));
+        assert!(entry.is_fresh(Duration::from_secs(60 * 60)));
+    }
+}
@@ -1,11 +1,176 @@
-use crate::config::OsrsConfig;
-use comfy_table::Table;
-use std::{fmt::Arguments, io::Write};
+use crate::{
+    config::OsrsConfig,
+    utils::{
+        http::{self, CachePolicy},
+        output::{ColorMode, OutputFormat},
+        terminal, time, trace,
+    },
+};
+use comfy_table::{ContentArrangement, Table};
+use serde::Serialize;
+use std::{
+    fmt::Arguments,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use structopt::StructOpt;
+
+/// Options that apply to every command. These are defined at the top level of
+/// the CLI, but can be given after any subcommand.
+#[derive(Clone, Debug, Default, StructOpt)]
+pub struct GlobalOptions {
+    /// The format to print command output in. Machine-readable formats
+    /// (json, ndjson, csv) print raw values instead of formatted text.
+    #[structopt(
+        long,
+        global = true,
+        default_value = "text",
+        possible_values = OutputFormat::NAMES,
+        case_insensitive = true
+    )]
+    pub format: OutputFormat,
+
+    /// When to use colors and other styling in text output. In auto mode,
+    /// output is styled if it's going to a terminal and NO_COLOR isn't set.
+    #[structopt(
+        long,
+        global = true,
+        default_value = "auto",
+        possible_values = ColorMode::NAMES,
+        case_insensitive = true
+    )]
+    pub color: ColorMode,
+
+    /// Ignore cached API responses and fetch fresh data. The new data will
+    /// still be cached.
+    #[structopt(long, global = true, conflicts_with = "offline")]
+    pub refresh: bool,
+
+    /// Never make API requests for cacheable data, and only use what's already
+    /// cached, regardless of age
+    #[structopt(long, global = true)]
+    pub offline: bool,
+
+    /// Path to a config file to use instead of the default one. Changes made
+    /// with `osrs config set` will also be written here.
+    #[structopt(long, global = true, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Name of the config profile to use for this command, instead of the
+    /// active one. Profiles can override the default player and account type.
+    #[structopt(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Print details about what's happening under the hood, such as which
+    /// HTTP requests are made, their status and timing, and whether responses
+    /// come from the cache. Details are printed to stderr, or the browser
+    /// console on the website.
+    #[structopt(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Never send long output through a pager. By default, text output that
+    /// doesn't fit on the screen is shown with `$PAGER` (or `less`).
+    #[structopt(long, global = true)]
+    pub no_pager: bool,
+
+    /// Send the command's output to a webhook from the `webhooks` config
+    /// field, e.g. a Discord channel. Nothing is sent if the command fails or
+    /// prints nothing.
+    #[structopt(long, global = true, value_name = "target")]
+    pub notify: Option<String>,
+
+    /// Re-run the command on an interval (e.g. `30s`, `5m` or `1h`) until
+    /// stopped with Ctrl-C. Output is redrawn each time, with anything that
+    /// changed since the previous run highlighted. With `--notify`, output is
+    /// only sent when it changes.
+    #[structopt(
+        long,
+        global = true,
+        value_name = "interval",
+        parse(try_from_str = time::parse_duration)
+    )]
+    pub watch: Option<Duration>,
+}
+
+impl GlobalOptions {
+    /// Fill in any options that weren't given with values from `defaults`.
+    /// There's no way to tell if an option was given explicitly with its
+    /// default value, so default values are always replaced.
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn with_defaults(self, defaults: &Self) -> Self {
+        Self {
+            format: if self.format == OutputFormat::default() {
+                defaults.format
+            } else {
+                self.format
+            },
+            color: if self.color == ColorMode::default() {
+                defaults.color
+            } else {
+                self.color
+            },
+            refresh: self.refresh || defaults.refresh,
+            offline: self.offline || defaults.offline,
+            config: self.config.or_else(|| defaults.config.clone()),
+            profile: self.profile.or_else(|| defaults.profile.clone()),
+            verbose: self.verbose || defaults.verbose,
+            no_pager: self.no_pager || defaults.no_pager,
+            notify: self.notify.or_else(|| defaults.notify.clone()),
+            watch: self.watch.or(defaults.watch),
+        }
+    }
+
+    /// Options to pass down to commands run by this one, e.g. from a script.
+    /// Options that apply to the outer command's output as a whole
+    /// (`--notify` and `--watch`) are left out, since the outer command
+    /// already handles them.
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn for_nested(&self) -> Self {
+        Self {
+            notify: None,
+            watch: None,
+            ..self.clone()
+        }
+    }
+
+    /// Options for one run of a command in watch mode, starting at 1. After
+    /// the first run, cached data is probably from an earlier run, so it's
+    /// always fetched again, unless running offline.
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn for_watch_run(&self, run: usize) -> Self {
+        Self {
+            refresh: self.refresh || run > 1,
+            ..self.clone()
+        }
+    }
+
+    /// Get the caching behavior requested by the user
+    pub fn cache_policy(&self) -> CachePolicy {
+        if self.offline {
+            CachePolicy::Offline
+        } else if self.refresh {
+            CachePolicy::Refresh
+        } else {
+            CachePolicy::Normal
+        }
+    }
+}
 
 /// A helper type to encapsulate values that we are likely to use multiple
 /// time while executing a command. Centralizes that logic to clean shit up.
 pub struct CommandContext<O: Write> {
     pub config: OsrsConfig,
+    /// The global options that the command was run with. Commands that run
+    /// other commands can use these as defaults for the nested commands.
+    pub options: GlobalOptions,
+    /// The format that command output should be written in
+    pub format: OutputFormat,
+    /// Should text output include colors and other styling?
+    pub color: bool,
+    /// Maximum width of text output, in columns. `None` if unknown, e.g.
+    /// when output isn't going to a terminal.
+    pub width: Option<u16>,
     /// Output that we send to the user. On native platforms this will
     /// generally be stdout, on others (e.g. web) it could be a byte vector or
     /// similar. This is passed from the caller, so they get to decide what we
@@ -15,17 +180,119 @@ pub struct CommandContext<O: Write> {
 
 impl<O: Write> CommandContext<O> {
     /// Load initial context from given output. Config will be loaded
-    /// automatically from disk/browser storage.
-    pub fn load(output: O) -> anyhow::Result<Self> {
-        let config = OsrsConfig::load()?;
+    /// automatically from disk/browser storage, with overrides from the
+    /// environment and the selected profile.
+    pub fn load(output: O, options: &GlobalOptions) -> anyhow::Result<Self> {
+        Self::with_config(output, options, load_config(options)?)
+    }
+
+    /// Create a context with a config that's already loaded, e.g. by a shell
+    /// that runs many commands. Global settings from the options are applied
+    /// the same way as in [Self::load].
+    pub fn with_config(
+        output: O,
+        options: &GlobalOptions,
+        config: OsrsConfig,
+    ) -> anyhow::Result<Self> {
+        // Enable tracing first, so it covers everything else
+        trace::set_enabled(options.verbose);
+        http::set_cache_policy(options.cache_policy());
+        crate::configure(&config)?;
+        // Styled text is generated all over the place, so apply the setting
+        // globally. This also overrides the library's own TTY detection,
+        // which doesn't work in the browser.
+        let color = options.color.is_enabled();
+        colored::control::set_override(color);
 
-        Ok(CommandContext { config, output })
+        Ok(CommandContext {
+            config,
+            options: options.clone(),
+            format: options.format,
+            color,
+            width: terminal::width(),
+            output,
+        })
+    }
+
+    /// Create a context with default config, for tests. Unlike [Self::load],
+    /// this doesn't touch global state, so the caller is responsible for
+    /// setting the HTTP transport if needed.
+    #[cfg(all(test, not(target_family = "wasm")))]
+    pub fn for_test(output: O, format: OutputFormat) -> Self {
+        CommandContext {
+            config: OsrsConfig::default(),
+            options: GlobalOptions {
+                format,
+                ..Default::default()
+            },
+            format,
+            color: false,
+            width: None,
+            output,
+        }
+    }
+
+    /// Create a context for running another command with the same config
+    /// and options as this one, but writing to a different output
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn with_output<O2: Write>(&self, output: O2) -> CommandContext<O2> {
+        CommandContext {
+            config: self.config.clone(),
+            options: self.options.clone(),
+            format: self.format,
+            color: self.color,
+            width: self.width,
+            output,
+        }
     }
 
     pub fn config(&self) -> &OsrsConfig {
         &self.config
     }
 
+    /// Get the config for a command run by this one, with the given options.
+    /// The loaded config is reused, unless the options ask for a different
+    /// config file or profile.
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn config_for(
+        &self,
+        options: &GlobalOptions,
+    ) -> anyhow::Result<OsrsConfig> {
+        if options.config == self.options.config
+            && options.profile == self.options.profile
+        {
+            Ok(self.config.clone())
+        } else {
+            load_config(options)
+        }
+    }
+
+    /// Load the config again, e.g. after a command changed it
+    #[cfg_attr(target_family = "wasm", allow(unused))]
+    pub fn reload_config(&mut self) -> anyhow::Result<()> {
+        self.config = load_config(&self.options)?;
+        Ok(())
+    }
+
+    /// Custom location of the config file, if the user gave one. This should
+    /// be used when loading or saving config directly.
+    pub fn config_path(&self) -> Option<&Path> {
+        self.options.config.as_deref()
+    }
+
+    /// The requested output format. Commands should check this to decide
+    /// whether to print human-readable text or structured records.
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Should text output include colors and other styling? Styling itself
+    /// is controlled globally, so this is only needed for output that
+    /// describes the styling.
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
     /// Print data to output, followed by a newline
     pub fn println(&mut self, data: &str) -> anyhow::Result<()> {
         self.output.write_all(data.as_bytes())?;
@@ -40,10 +307,49 @@ impl<O: Write> CommandContext<O> {
         Ok(())
     }
 
-    /// Print a pretty table to output
-    pub fn print_table(&mut self, table: &Table) -> anyhow::Result<()> {
-        // TODO fix colors
-        self.println(&table.to_string())?;
+    /// Print a pretty table to output, drawn in the configured style. Any
+    /// styling should be applied to cell content with [colored], rather than
+    /// with the table's own styling options, so that it respects the color
+    /// setting and works in the browser. If the output width is known, the
+    /// table is fit to it by wrapping cell content.
+    pub fn print_table(&mut self, table: &mut Table) -> anyhow::Result<()> {
+        let style = self.config.table_style;
+        if let Some(width) = self.width.filter(|_| style.fits_terminal()) {
+            table
+                .set_content_arrangement(ContentArrangement::Dynamic)
+                .set_width(width);
+        }
+        // Discord tables are split into multiple messages. Put a blank line
+        // between them so they're easy to tell apart.
+        for (i, block) in style.render(table).into_iter().enumerate() {
+            if i > 0 {
+                self.println("")?;
+            }
+            self.println(&block)?;
+        }
         Ok(())
     }
+
+    /// Print a single structured record in the requested machine-readable
+    /// format
+    pub fn print_record<T: Serialize>(
+        &mut self,
+        record: &T,
+    ) -> anyhow::Result<()> {
+        self.format.write_record(&mut self.output, record)
+    }
+
+    /// Print a list of structured records in the requested machine-readable
+    /// format
+    pub fn print_records<T: Serialize>(
+        &mut self,
+        records: &[T],
+    ) -> anyhow::Result<()> {
+        self.format.write_records(&mut self.output, records)
+    }
+}
+
+/// Load the config from storage, with the profile from the options applied
+pub fn load_config(options: &GlobalOptions) -> anyhow::Result<OsrsConfig> {
+    OsrsConfig::load(options.config.as_deref(), options.profile.as_deref())
 }
@@ -1,6 +1,7 @@
 //! Utilities related to formatting values into strings
 
 use num_format::{Locale, ToFormattedString};
+use std::time::Duration;
 
 /// Format the given number.
 pub fn fmt_int<T: ToFormattedString>(num: &T) -> String {
@@ -15,6 +16,7 @@ pub fn fmt_int<T: ToFormattedString>(num: &T) -> String {
 /// Format a GE price. Prices are typically options since any item could
 /// potentially have no trade data, so will format `None` as a dash. Otherwise,
 /// the price will be formatted as an int (with commas).
+#[cfg(feature = "cli")]
 pub fn fmt_price(price: Option<usize>) -> String {
     match price {
         Some(price) => fmt_int(&price),
@@ -24,12 +26,41 @@ pub fn fmt_price(price: Option<usize>) -> String {
 }
 
 /// Format a probability value (0 to 1) into a percentage string.
+#[cfg(feature = "cli")]
 pub fn fmt_probability(probability: f64) -> String {
     format!("{:.1}%", probability * 100.0)
 }
 
 /// Format a probability value (0 to 1) into a percentage string, with extra
 /// decimal places.
+#[cfg(feature = "cli")]
 pub fn fmt_probability_long(probability: f64) -> String {
     format!("{:.4}%", probability * 100.0)
 }
+
+/// Format a duration in the largest unit that fits it, e.g. `3m` or `2d`.
+/// Precision is thrown away, this is just meant to give a rough idea.
+pub fn fmt_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt_duration() {
+        assert_eq!(fmt_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(fmt_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(fmt_duration(Duration::from_secs(60)), "1m");
+        assert_eq!(fmt_duration(Duration::from_secs(3599)), "59m");
+        assert_eq!(fmt_duration(Duration::from_secs(7200)), "2h");
+        assert_eq!(fmt_duration(Duration::from_secs(3 * 86400 + 5)), "3d");
+    }
+}
@@ -1,29 +1,372 @@
 //! Utilities related to HTTP requests
 
+use crate::{
+    config::OsrsConfig,
+    error::OsrsError,
+    utils::{
+        cache::{CacheEntry, PersistentCache},
+        fmt, time,
+        trace::trace,
+        transport::{self, Transport},
+    },
+};
+use futures::future::{self, Either};
 use reqwest::{
     header::{HeaderMap, HeaderValue},
-    Client,
+    Client, Response, StatusCode, Url,
 };
 use serde::de::DeserializeOwned;
 use std::{
+    collections::HashMap,
+    future::Future,
     ops::Deref,
-    sync::{RwLock, RwLockReadGuard, TryLockError},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock, RwLockReadGuard, TryLockError,
+    },
+    time::Duration,
 };
 
+/// How [HttpCache] should use the persistent cache. This is set once per
+/// command, based on CLI args. It's global so we don't have to plumb it into
+/// every cache instance.
+static CACHE_POLICY: RwLock<CachePolicy> = RwLock::new(CachePolicy::Normal);
+
+/// Rules for when to use persisted responses vs fetching fresh ones
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Use cached data if it's fresh, otherwise fetch
+    #[default]
+    Normal,
+    /// Always fetch, ignoring cached data. The new responses will still be
+    /// cached.
+    Refresh,
+    /// Never fetch. Use cached data regardless of age, and fail if there is
+    /// none.
+    Offline,
+}
+
+/// Set the persistent cache policy for all subsequent requests
+#[cfg(feature = "cli")]
+pub fn set_cache_policy(policy: CachePolicy) {
+    // Lock can only be poisoned by a panic while setting, which can't happen
+    *CACHE_POLICY.write().unwrap() = policy;
+}
+
+fn cache_policy() -> CachePolicy {
+    *CACHE_POLICY.read().unwrap()
+}
+
+/// Incremented to discard the in-memory data of every [HttpCache]. Each cache
+/// remembers the generation its data was loaded in, and reloads if it's old.
+static CACHE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Discard the in-memory data of every [HttpCache], so it's loaded again (from
+/// the persistent cache or HTTP) the next time it's used. Watch mode does this
+/// before each run, otherwise the data could never change between runs.
+#[cfg(feature = "cli")]
+#[cfg_attr(target_family = "wasm", allow(unused))]
+pub fn clear_memory_caches() {
+    CACHE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Settings for requests over the network. This is set once per command,
+/// based on config. `None` means the defaults from [OsrsConfig] haven't been
+/// overridden.
+static HTTP_SETTINGS: RwLock<Option<HttpSettings>> = RwLock::new(None);
+
+/// The time of the next request that's allowed to each host, in milliseconds
+/// since the Unix epoch. Used to space out requests to the same host.
+static NEXT_REQUEST_TIMES: Mutex<Option<HashMap<String, u64>>> =
+    Mutex::new(None);
+
+/// Limits and retry behavior for requests over the network
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpSettings {
+    /// Maximum time to wait for a single request. `None` means no limit.
+    pub timeout: Option<Duration>,
+    /// Maximum number of times to retry a failed request
+    pub retries: u32,
+    /// Delay before the first retry. Doubles for each subsequent retry.
+    pub retry_delay: Duration,
+    /// Minimum time between requests to the same host
+    pub min_interval: Duration,
+}
+
+impl HttpSettings {
+    pub fn from_config(config: &OsrsConfig) -> Self {
+        Self {
+            timeout: match config.http_timeout_secs {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            retries: config.http_retries.try_into().unwrap_or(u32::MAX),
+            retry_delay: Duration::from_millis(config.http_retry_delay_ms),
+            min_interval: Duration::from_millis(config.http_min_interval_ms),
+        }
+    }
+
+    /// Get the delay before the given retry (starting at 0 for the first
+    /// retry)
+    fn backoff(&self, retry: u32) -> Duration {
+        self.retry_delay.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+/// Set the network settings for all subsequent requests
+pub fn set_http_settings(settings: HttpSettings) {
+    *HTTP_SETTINGS.write().unwrap() = Some(settings);
+}
+
+fn http_settings() -> HttpSettings {
+    HTTP_SETTINGS
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| HttpSettings::from_config(&OsrsConfig::default()))
+}
+
 /// Perform an HTTP GET request. The response is assumed to be JSON (as that's
 /// what all current usages return).
 pub async fn get<T: DeserializeOwned>(
     path: &str,
     query_params: &[(&str, &str)],
 ) -> anyhow::Result<T> {
-    let response = http_client()?.get(path).query(query_params).send().await?;
-    let body = response.error_for_status()?.text().await?;
-    let json = serde_json::from_str(body.as_str())?;
-    Ok(json)
+    let body = get_text(path, query_params).await?;
+    Ok(parse_json(path, &body)?)
+}
+
+/// Perform an HTTP GET request, and return the response body as text. The
+/// response will come from the current [Transport], which may or may not
+/// actually use the network.
+pub async fn get_text(
+    path: &str,
+    query_params: &[(&str, &str)],
+) -> anyhow::Result<String> {
+    // parse_with_params will always append a `?`, even with no params
+    let url = if query_params.is_empty() {
+        Url::parse(path)?
+    } else {
+        Url::parse_with_params(path, query_params)?
+    };
+    get_via(&transport::transport(), &url).await
+}
+
+/// Get a response body from a specific transport, tracing the result
+async fn get_via(transport: &Transport, url: &Url) -> anyhow::Result<String> {
+    let start = time::now_millis();
+    let result = transport.get(url).await;
+    let elapsed = time::now_millis().saturating_sub(start);
+    match &result {
+        Ok(body) => trace(format_args!(
+            "GET {} returned {} bytes in {}ms",
+            url,
+            fmt::fmt_int(&body.len()),
+            elapsed
+        )),
+        Err(error) => trace(format_args!(
+            "GET {} failed in {}ms: {:#}",
+            url, elapsed, error
+        )),
+    }
+    result
+}
+
+/// Perform an HTTP GET request over the network, and return the response body
+/// as text. Generally you want [get] or [get_text] instead, which respect the
+/// current [Transport].
+///
+/// Requests to the same host are spaced out, and requests that fail for
+/// reasons that might be temporary (timeouts, connection errors, rate limits
+/// and server errors) are retried with exponential backoff. All GETs are
+/// idempotent, so retrying is always safe.
+pub async fn fetch(url: &Url) -> anyhow::Result<String> {
+    let settings = http_settings();
+    let mut retry = 0;
+    loop {
+        throttle(url, settings.min_interval).await;
+        match fetch_once(url, settings.timeout).await {
+            Err(error) if retry < settings.retries && is_retryable(&error) => {
+                let backoff = settings.backoff(retry);
+                trace(format_args!(
+                    "Retrying {} in {}ms (retry {} of {}): {:#}",
+                    url,
+                    backoff.as_millis(),
+                    retry + 1,
+                    settings.retries,
+                    error
+                ));
+                time::sleep(backoff).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Make a single request over the network, with no retries
+async fn fetch_once(
+    url: &Url,
+    timeout: Option<Duration>,
+) -> anyhow::Result<String> {
+    let request = async {
+        let response = http_client()?
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(OsrsError::UpstreamUnavailable)?;
+        Ok(check_status(url, response)?
+            .text()
+            .await
+            .map_err(OsrsError::UpstreamUnavailable)?)
+    };
+    with_timeout(url, timeout, request).await
+}
+
+/// Send a POST request with a JSON body over the network. Unlike GETs, this
+/// ignores the current [Transport], since it's sending
+/// something rather than loading data. It's also never retried, since
+/// repeating a POST could duplicate its effect.
+#[cfg(feature = "cli")]
+pub async fn post_json(
+    url: &str,
+    body: &serde_json::Value,
+) -> anyhow::Result<()> {
+    let url = Url::parse(url)?;
+    let settings = http_settings();
+    throttle(&url, settings.min_interval).await;
+    let start = time::now_millis();
+    let request = async {
+        let response = http_client()?
+            .post(url.clone())
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(OsrsError::UpstreamUnavailable)?;
+        check_status(&url, response)?;
+        Ok(())
+    };
+    let result = with_timeout(&url, settings.timeout, request).await;
+    let elapsed = time::now_millis().saturating_sub(start);
+    match &result {
+        Ok(()) => trace(format_args!("POST {} finished in {}ms", url, elapsed)),
+        Err(error) => trace(format_args!(
+            "POST {} failed in {}ms: {:#}",
+            url, elapsed, error
+        )),
+    }
+    result
+}
+
+/// Check the status of a response, turning any error status into an error
+fn check_status(url: &Url, response: Response) -> anyhow::Result<Response> {
+    let status = response.status();
+    trace(format_args!("{} responded with {}", url, status));
+    match response.error_for_status() {
+        Ok(response) => Ok(response),
+        // Categorize the error, so the user can tell if it's their fault
+        Err(error) => Err(if status == StatusCode::TOO_MANY_REQUESTS {
+            OsrsError::RateLimited(error)
+        } else if status.is_server_error() {
+            OsrsError::UpstreamUnavailable(error)
+        } else {
+            OsrsError::HttpStatus {
+                status: status.as_u16(),
+                source: error,
+            }
+        }
+        .into()),
+    }
+}
+
+/// Run a request, failing if it takes longer than the timeout
+async fn with_timeout<T>(
+    url: &Url,
+    timeout: Option<Duration>,
+    request: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return request.await,
+    };
+    // Reqwest doesn't support timeouts in wasm, so race against a timer
+    // instead
+    match future::select(Box::pin(request), Box::pin(time::sleep(timeout)))
+        .await
+    {
+        Either::Left((result, _)) => result,
+        Either::Right(((), _)) => Err(OsrsError::Timeout {
+            url: url.to_string(),
+            timeout,
+        }
+        .into()),
+    }
+}
+
+/// Could the error from a request be fixed by trying again later?
+fn is_retryable(error: &anyhow::Error) -> bool {
+    matches!(
+        OsrsError::find(error),
+        Some(
+            OsrsError::UpstreamUnavailable(_)
+                | OsrsError::RateLimited(_)
+                | OsrsError::Timeout { .. }
+        )
+    )
+}
+
+/// Wait until we're allowed to make another request to the URL's host
+async fn throttle(url: &Url, min_interval: Duration) {
+    let host = url.host_str().unwrap_or_default().to_owned();
+    let host_for_trace = host.clone();
+    let now = time::now_millis();
+    // Reserve a slot for this request before waiting, so concurrent requests
+    // get spaced out too
+    let wait = {
+        let mut next_request_times = NEXT_REQUEST_TIMES.lock().unwrap();
+        let next_request_time = next_request_times
+            .get_or_insert_with(HashMap::new)
+            .entry(host)
+            .or_default();
+        let start = (*next_request_time).max(now);
+        *next_request_time = start + min_interval.as_millis() as u64;
+        start - now
+    };
+    if wait > 0 {
+        trace(format_args!(
+            "Waiting {}ms before the next request to {}",
+            wait, host_for_trace
+        ));
+        time::sleep(Duration::from_millis(wait)).await;
+    }
+}
+
+/// Parse a JSON response body. The URL is just used for error context.
+fn parse_json<T: DeserializeOwned>(
+    url: &str,
+    body: &str,
+) -> Result<T, OsrsError> {
+    serde_json::from_str(body).map_err(|source| OsrsError::InvalidResponse {
+        url: url.into(),
+        source,
+    })
+}
+
+/// Append a path to a base URL, making sure there's exactly one slash between
+/// them. This is useful for base URLs from the config, which may or may not
+/// have a trailing slash.
+pub fn join_url(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
 }
 
 /// Build a URL from a base path and list of query params. Each param's value
 /// will be encoded
+#[cfg(feature = "cli")]
 pub fn url(path: &str, query_params: &[(&str, &str)]) -> String {
     let params_vec: Vec<String> = query_params
         .iter()
@@ -58,44 +401,71 @@ fn http_client() -> anyhow::Result<Client> {
 /// A write-once cache for an HTTP URL. The first time the value is requested,
 /// it will be fetched from the URL. All subsequent requests will be fetched
 /// from the cache. This guarantees that only one request will ever be made for
-/// the lifetime of this struct.
+/// the lifetime of this struct, unless it's refreshed, the in-memory data is
+/// cleared with [clear_memory_caches], or the URL changes.
+///
+/// The URL is given at load time rather than creation, because it typically
+/// comes from the config, which can change between commands (e.g. when
+/// switching profiles in the shell).
+///
+/// Responses are also stored in the [PersistentCache], so subsequent
+/// invocations of the program can re-use them until they're older than the
+/// TTL (subject to the global [CachePolicy]). Entries are keyed by URL too, so
+/// responses from one API are never served for another.
 pub struct HttpCache<T> {
-    url: String,
-    /// This will always be `Some` after the first request
-    data: RwLock<Option<T>>,
+    /// Name of this response, e.g. for trace output. The key in the
+    /// persistent cache is derived from this and the URL.
+    key: String,
+    /// How long a persisted response is considered fresh
+    ttl: Duration,
+    /// This will always be `Some` after the first request. The data is
+    /// stored with the URL it was loaded from.
+    data: RwLock<Option<(String, T)>>,
+    /// The [CACHE_GENERATION] that the data was loaded in
+    generation: AtomicU64,
 }
 
 impl<T: DeserializeOwned> HttpCache<T> {
-    /// Create a new cache wrapper for the given URL
-    pub fn new(url: String) -> Self {
+    /// Create a new cache wrapper. The key must be unique among all caches
+    pub fn new(key: &str, ttl: Duration) -> Self {
         Self {
-            url,
+            key: key.into(),
+            ttl,
             data: RwLock::new(None),
+            generation: AtomicU64::new(0),
         }
     }
 
     /// Load the value via HTTP if necessary, then return it. The returned
     /// value will be wrapped in a guard value that implements `Deref` to
     /// expose its inner value, meaning it can only be obtained by reference.
-    pub async fn load(&self) -> anyhow::Result<CacheGuard<'_, T>> {
-        /// TryLockError doesn't implement Send because it carries the guard,
-        /// which is really annoying. To get around that we throw the error
-        /// itself away and just hold the string. This is kinda shitty but
-        /// we shouldn't ever actually hit this error because the program is
-        /// single-threaded, so the lock can't be blocked or poisoned.
-        fn map_lock_err<T>(err: TryLockError<T>) -> anyhow::Error {
-            anyhow::Error::msg(err.to_string())
-        }
+    pub async fn load(&self, url: &str) -> anyhow::Result<CacheGuard<'_, T>> {
+        self.load_with(url, cache_policy(), &transport::transport())
+            .await
+    }
 
+    /// [Self::load], with an explicit cache policy and transport rather than
+    /// the global ones
+    pub(super) async fn load_with(
+        &self,
+        url: &str,
+        policy: CachePolicy,
+        transport: &Transport,
+    ) -> anyhow::Result<CacheGuard<'_, T>> {
         // Check if the data is populated. We'll immediately release the lock,
         // which will let us populated the cache if it isn't already.
-        let is_loaded = self.data.try_read().map_err(map_lock_err)?.is_some();
+        let is_loaded = matches!(
+            &*self.data.try_read().map_err(map_lock_err)?,
+            Some((loaded_url, _)) if loaded_url == url
+        ) && self.generation.load(Ordering::Relaxed)
+            == CACHE_GENERATION.load(Ordering::Relaxed);
 
-        if !is_loaded {
-            // Load the data from HTTP, then store it in the cache
-            let response = get(&self.url, &[]).await?;
-            let mut data_ref = self.data.try_write().map_err(map_lock_err)?;
-            *data_ref = Some(response);
+        if is_loaded {
+            trace(format_args!("Using {} from memory", self.key));
+        } else {
+            // Load the data from disk or HTTP, then store it in the cache
+            let response = self.fetch(url, policy, transport).await?;
+            self.store(url, response)?;
         }
 
         let guard = self.data.try_read().map_err(map_lock_err)?;
@@ -103,6 +473,121 @@ impl<T: DeserializeOwned> HttpCache<T> {
         // it. This lets the guard freely unwrap the inner cache value.
         Ok(CacheGuard(guard))
     }
+
+    /// Fetch the value again, even if it's already loaded, and replace the
+    /// loaded value. The persistent cache is skipped too, unless running
+    /// offline. Long-running processes can use this to keep data up to date.
+    pub async fn refresh(&self, url: &str) -> anyhow::Result<()> {
+        let policy = match cache_policy() {
+            CachePolicy::Offline => CachePolicy::Offline,
+            CachePolicy::Normal | CachePolicy::Refresh => CachePolicy::Refresh,
+        };
+        let response = self.fetch(url, policy, &transport::transport()).await?;
+        self.store(url, response)
+    }
+
+    /// Replace the in-memory data
+    fn store(&self, url: &str, data: T) -> anyhow::Result<()> {
+        *self.data.try_write().map_err(map_lock_err)? =
+            Some((url.into(), data));
+        self.generation
+            .store(CACHE_GENERATION.load(Ordering::Relaxed), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Get the key for a URL's response in the persistent cache. The URL is
+    /// hashed, since keys are used as file names.
+    pub(super) fn persistent_key(&self, url: &str) -> String {
+        format!("{}-{:016x}", self.key, hash_url(url))
+    }
+
+    /// Get the data from the persistent cache if possible (according to the
+    /// given policy), otherwise fetch it via HTTP and persist the response
+    async fn fetch(
+        &self,
+        url: &str,
+        policy: CachePolicy,
+        transport: &Transport,
+    ) -> anyhow::Result<T> {
+        // Fixture transports bypass the persistent cache, so recordings always
+        // capture real responses and replays are deterministic
+        if !matches!(transport, Transport::Network) {
+            trace(format_args!(
+                "Skipping persistent cache for {} in record/replay mode",
+                self.key
+            ));
+            let body = get_via(transport, &Url::parse(url)?).await?;
+            return Ok(parse_json(url, &body)?);
+        }
+
+        let persistent_key = self.persistent_key(url);
+        if policy == CachePolicy::Refresh {
+            trace(format_args!("Ignoring cached {} (refresh)", self.key));
+        } else {
+            // If the entry is unreadable or corrupt, just treat it as a miss
+            match PersistentCache::get(&persistent_key) {
+                Ok(Some(entry))
+                    if policy == CachePolicy::Offline
+                        || entry.is_fresh(self.ttl) =>
+                {
+                    if let Ok(data) = serde_json::from_str(&entry.body) {
+                        trace(format_args!(
+                            "Using cached {} (fetched {} ago)",
+                            self.key,
+                            fmt::fmt_duration(entry.age())
+                        ));
+                        return Ok(data);
+                    }
+                    trace(format_args!("Cached {} is corrupt", self.key));
+                }
+                Ok(Some(entry)) => trace(format_args!(
+                    "Cached {} is stale (fetched {} ago)",
+                    self.key,
+                    fmt::fmt_duration(entry.age())
+                )),
+                Ok(None) => {
+                    trace(format_args!("No cached {}", self.key));
+                }
+                Err(error) => trace(format_args!(
+                    "Error reading cached {}: {:#}",
+                    self.key, error
+                )),
+            }
+        }
+
+        if policy == CachePolicy::Offline {
+            return Err(OsrsError::NotCached(self.key.clone()).into());
+        }
+
+        let body = get_via(transport, &Url::parse(url)?).await?;
+        let data = parse_json(url, &body)?;
+        // Failing to persist the response shouldn't fail the command, we'll
+        // just have to fetch it again next time
+        if let Err(error) =
+            PersistentCache::set(&persistent_key, &CacheEntry::new(body))
+        {
+            trace(format_args!("Error caching {}: {:#}", self.key, error));
+        }
+        Ok(data)
+    }
+}
+
+/// Hash a URL with 64-bit FNV-1a. Unlike the standard library's hasher, this
+/// is guaranteed to give the same result across runs and Rust versions, so it
+/// can be used in persistent cache keys.
+fn hash_url(url: &str) -> u64 {
+    url.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// TryLockError doesn't implement Send because it carries the guard, which is
+/// really annoying. To get around that we throw the error itself away and just
+/// hold the string. This is kinda shitty but we shouldn't ever actually hit
+/// this error because the program is single-threaded, so the lock can't be
+/// blocked or poisoned.
+fn map_lock_err<T>(err: TryLockError<T>) -> anyhow::Error {
+    anyhow::Error::msg(err.to_string())
 }
 
 /// A thin wrapper around the RwLock guard that will mask the fact that the
@@ -111,7 +596,7 @@ impl<T: DeserializeOwned> HttpCache<T> {
 /// option in this guard rather than forcing the caller to do it. This is a
 /// little annoying but there are no other options since `RwLockReadGuard`
 /// doesn't have a `map` function.
-pub struct CacheGuard<'a, T>(RwLockReadGuard<'a, Option<T>>);
+pub struct CacheGuard<'a, T>(RwLockReadGuard<'a, Option<(String, T)>>);
 
 impl<T> Deref for CacheGuard<'_, T> {
     type Target = T;
@@ -119,6 +604,92 @@ impl<T> Deref for CacheGuard<'_, T> {
     fn deref(&self) -> &Self::Target {
         // This guard should only ever be constructed around a populated cache,
         // so this unwrap is safe
-        self.0.as_ref().unwrap()
+        &self.0.as_ref().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let settings = HttpSettings {
+            timeout: None,
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+            min_interval: Duration::ZERO,
+        };
+        assert_eq!(settings.backoff(0), Duration::from_millis(500));
+        assert_eq!(settings.backoff(1), Duration::from_millis(1000));
+        assert_eq!(settings.backoff(2), Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(
+            &OsrsError::Timeout {
+                url: "http://localhost".into(),
+                timeout: Duration::from_secs(1),
+            }
+            .into()
+        ));
+        assert!(!is_retryable(
+            &OsrsError::PlayerNotFound("Zezima".into()).into()
+        ));
+        assert!(!is_retryable(&anyhow::anyhow!("oh no")));
+    }
+
+    #[test]
+    fn test_hash_url() {
+        assert_eq!(hash_url(""), 0xcbf29ce484222325);
+        assert_eq!(hash_url("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(
+            hash_url("http://localhost:8000/latest"),
+            hash_url("http://localhost:8001/latest")
+        );
+    }
+
+    /// Responses from different URLs are persisted separately, so switching
+    /// APIs doesn't serve data from the old one
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_persistent_key_includes_url() {
+        let dir = PersistentCache::use_test_dir("url_key");
+        let cache: HttpCache<u32> =
+            HttpCache::new("test_url_key", Duration::from_secs(60));
+        let url = "http://localhost:8000/latest";
+        let other_url = "http://localhost:8001/latest";
+        let key = cache.persistent_key(url);
+        assert_ne!(key, cache.persistent_key(other_url));
+        PersistentCache::set(&key, &CacheEntry::new("1".into())).unwrap();
+
+        // Offline, so nothing is fetched over the network
+        let result = cache
+            .load_with(url, CachePolicy::Offline, &Transport::Network)
+            .await
+            .map(|data| *data);
+        let other_result = cache
+            .load_with(other_url, CachePolicy::Offline, &Transport::Network)
+            .await
+            .map(|data| *data);
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(result.unwrap(), 1);
+        assert!(matches!(
+            OsrsError::find(&other_result.unwrap_err()),
+            Some(OsrsError::NotCached(_))
+        ));
+    }
+
+    #[test]
+    fn test_join_url() {
+        assert_eq!(
+            join_url("http://localhost:8000", "mapping"),
+            "http://localhost:8000/mapping"
+        );
+        assert_eq!(
+            join_url("http://localhost:8000/api/", "/mapping"),
+            "http://localhost:8000/api/mapping"
+        );
     }
 }
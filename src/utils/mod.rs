@@ -1,10 +1,25 @@
+#[cfg(feature = "cli")]
+pub mod alias;
 #[cfg(target_family = "wasm")]
 pub mod browser;
+pub mod cache;
+#[cfg(feature = "cli")]
 pub mod context;
 pub mod fmt;
-pub mod hiscore;
 pub mod http;
-pub mod item;
-pub mod math;
+pub mod notify;
+#[cfg(feature = "cli")]
+pub mod output;
+#[cfg(all(feature = "cli", not(target_family = "wasm")))]
+pub mod pager;
+#[cfg(all(feature = "cli", not(target_family = "wasm")))]
+pub mod plugin;
 pub mod skill;
 pub mod table;
+#[cfg(feature = "cli")]
+pub mod terminal;
+pub mod time;
+pub mod trace;
+pub mod transport;
+#[cfg(all(feature = "cli", not(target_family = "wasm")))]
+pub mod watch;
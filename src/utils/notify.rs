@@ -0,0 +1,263 @@
+//! Notifications, which send command output to webhooks. This makes it
+//! possible to post lookups and alerts to e.g. a Discord channel or a bot.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "cli")]
+use {
+    crate::{
+        config::OsrsConfig,
+        error::OsrsError,
+        utils::{
+            http,
+            table::{self, CODE_FENCE},
+            trace::trace,
+        },
+    },
+    serde_json::json,
+    std::io::{self, Write},
+};
+
+/// A webhook that command output can be sent to, configured in the
+/// `webhooks` config field
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    /// URL that output is POSTed to
+    pub url: String,
+    /// What the request body looks like
+    #[serde(default)]
+    pub kind: WebhookKind,
+}
+
+/// Format of the request body for a webhook
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    /// `{"output": "..."}`, for custom receivers
+    #[default]
+    Json,
+    /// A Discord webhook message. Output is wrapped in a code block, and long
+    /// output is split into multiple messages.
+    Discord,
+}
+
+#[cfg(feature = "cli")]
+impl Webhook {
+    /// Find a webhook in the config by name
+    pub fn find<'a>(
+        config: &'a OsrsConfig,
+        name: &str,
+    ) -> Result<&'a Self, OsrsError> {
+        config.webhooks.get(name).ok_or_else(|| {
+            let available: Vec<&str> =
+                config.webhooks.keys().map(String::as_str).collect();
+            OsrsError::ArgsError(format!(
+                "Unknown notification target `{}`. Configured webhooks: {}. \
+                Add one with `osrs config set webhooks.{} '{{\"url\": \
+                \"...\"}}'`",
+                name,
+                if available.is_empty() {
+                    "none".into()
+                } else {
+                    available.join(", ")
+                },
+                name
+            ))
+        })
+    }
+
+    /// Send command output to this webhook. Colors and other styling are
+    /// stripped out. If there's no output, nothing is sent, so commands that
+    /// only print when something happens (e.g. price alerts) only notify when
+    /// it happens.
+    pub async fn send(&self, output: &str) -> anyhow::Result<()> {
+        let output = strip_ansi(output);
+        if output.trim().is_empty() {
+            trace(format_args!("No output, skipping notification"));
+            return Ok(());
+        }
+        for body in self.bodies(&output) {
+            http::post_json(&self.url, &body).await?;
+        }
+        Ok(())
+    }
+
+    /// Build the request body (or bodies) to send for some output
+    fn bodies(&self, output: &str) -> Vec<serde_json::Value> {
+        match self.kind {
+            WebhookKind::Json => vec![json!({ "output": output })],
+            WebhookKind::Discord => {
+                // With the Discord table style, tables are already in code
+                // blocks. Remove those fences so we don't nest blocks.
+                let output: Vec<&str> = output
+                    .trim_end()
+                    .lines()
+                    .filter(|line| *line != CODE_FENCE)
+                    .collect();
+                table::discord_code_blocks(&output.join("\n"))
+                    .into_iter()
+                    .map(|content| json!({ "content": content }))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A writer that passes output through to another writer, and also keeps a
+/// copy of it to send as a notification
+#[cfg(feature = "cli")]
+pub struct Tee<O: Write> {
+    inner: O,
+    copy: Vec<u8>,
+}
+
+#[cfg(feature = "cli")]
+impl<O: Write> Tee<O> {
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            copy: Vec::new(),
+        }
+    }
+
+    /// Get everything that's been written so far
+    pub fn copy(&self) -> String {
+        String::from_utf8_lossy(&self.copy).into_owned()
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<O: Write> Write for Tee<O> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(data)?;
+        self.copy.extend_from_slice(&data[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Remove ANSI escape sequences (colors, bold, etc.) from text
+#[cfg(feature = "cli")]
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip `[`, any parameters, and the final byte, which is always
+            // in the range @ to ~
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(all(test, feature = "cli", not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn webhook(url: &str, kind: WebhookKind) -> Webhook {
+        Webhook {
+            url: url.into(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let mut config = OsrsConfig::default();
+        config
+            .webhooks
+            .insert("clan".into(), webhook("http://a", WebhookKind::Discord));
+        assert_eq!(
+            Webhook::find(&config, "clan").unwrap().kind,
+            WebhookKind::Discord
+        );
+        let error = Webhook::find(&config, "nope").unwrap_err().to_string();
+        assert!(error.contains("Configured webhooks: clan."), "{}", error);
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("plain"), "plain");
+        assert_eq!(
+            strip_ansi("\x1b[1;32mLevel 99\x1b[0m Attack"),
+            "Level 99 Attack"
+        );
+    }
+
+    #[test]
+    fn test_bodies() {
+        let output = "```\n+---+\n| a |\n+---+\n```\n";
+        assert_eq!(
+            webhook("", WebhookKind::Json).bodies(output),
+            vec![json!({ "output": output })]
+        );
+        assert_eq!(
+            webhook("", WebhookKind::Discord).bodies(output),
+            vec![json!({ "content": "```\n+---+\n| a |\n+---+\n```" })]
+        );
+
+        // Long output is split into multiple messages
+        let output = "Shark 1,000\n".repeat(500);
+        let bodies = webhook("", WebhookKind::Discord).bodies(&output);
+        assert!(bodies.len() > 1);
+        for body in bodies {
+            assert!(body["content"].as_str().unwrap().chars().count() <= 2000);
+        }
+    }
+
+    /// Send notifications to a real local receiver
+    #[tokio::test]
+    async fn test_send() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr());
+        // Receive both requests on another thread, since the server blocks
+        let receiver = thread::spawn(move || {
+            (0..2)
+                .map(|_| {
+                    let mut request = server.recv().unwrap();
+                    let mut body = String::new();
+                    request.as_reader().read_to_string(&mut body).unwrap();
+                    let received = (request.method().to_string(), body);
+                    request.respond(tiny_http::Response::empty(204)).unwrap();
+                    received
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let json_hook = webhook(&url, WebhookKind::Json);
+        let discord_hook = webhook(&url, WebhookKind::Discord);
+        json_hook
+            .send("\x1b[32mShark\x1b[0m 1,000\n")
+            .await
+            .unwrap();
+        // Empty output doesn't send anything
+        discord_hook.send("\n").await.unwrap();
+        discord_hook.send("Shark 1,000\n").await.unwrap();
+
+        let received = receiver.join().unwrap();
+        assert_eq!(
+            received,
+            vec![
+                ("POST".into(), r#"{"output":"Shark 1,000\n"}"#.into()),
+                (
+                    "POST".into(),
+                    r#"{"content":"```\nShark 1,000\n```"}"#.into()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Utilities for controlling how command output is written
+
+use crate::error::OsrsError;
+use derive_more::Display;
+use serde::Serialize;
+use std::{io::Write, str::FromStr};
+
+/// The format that command output is written in. Text is the default, and is
+/// intended for humans. All other formats are intended for scripts, and will
+/// contain raw (unformatted) values.
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text and tables
+    #[default]
+    #[display(fmt = "text")]
+    Text,
+    /// A single JSON document
+    #[display(fmt = "json")]
+    Json,
+    /// Newline-delimited JSON, one record per line
+    #[display(fmt = "ndjson")]
+    Ndjson,
+    /// CSV, with a header row
+    #[display(fmt = "csv")]
+    Csv,
+}
+
+impl OutputFormat {
+    /// All supported format names, for CLI help
+    pub const NAMES: &'static [&'static str] =
+        &["text", "json", "ndjson", "csv"];
+
+    /// Is this format meant to be parsed by a machine?
+    pub fn is_machine(self) -> bool {
+        self != Self::Text
+    }
+
+    /// Serialize a single record into the output. For JSON, this will be an
+    /// object rather than an array.
+    pub fn write_record<T: Serialize>(
+        self,
+        output: impl Write,
+        record: &T,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Text | Self::Json => {
+                write_json_line(output, record, true)?;
+            }
+            Self::Ndjson | Self::Csv => {
+                self.write_records(output, std::slice::from_ref(record))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a list of records into the output. Every record should have
+    /// the same set of fields, so that they can be written as CSV rows.
+    pub fn write_records<T: Serialize>(
+        self,
+        mut output: impl Write,
+        records: &[T],
+    ) -> anyhow::Result<()> {
+        match self {
+            // Text output should be rendered by the caller, but if we got here
+            // then JSON is the most readable fallback
+            Self::Text | Self::Json => {
+                write_json_line(output, &records, true)?;
+            }
+            Self::Ndjson => {
+                for record in records {
+                    write_json_line(&mut output, record, false)?;
+                }
+            }
+            Self::Csv => {
+                let mut writer = csv::Writer::from_writer(output);
+                for record in records {
+                    writer.serialize(record)?;
+                }
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = OsrsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            _ => Err(OsrsError::ArgsError(format!(
+                "Unknown output format: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// When to apply colors and other ANSI styling to text output
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Style output if it's going to a terminal, unless `NO_COLOR` is set
+    #[default]
+    #[display(fmt = "auto")]
+    Auto,
+    /// Always style output
+    #[display(fmt = "always")]
+    Always,
+    /// Never style output
+    #[display(fmt = "never")]
+    Never,
+}
+
+impl ColorMode {
+    /// All supported mode names, for CLI help
+    pub const NAMES: &'static [&'static str] = &["auto", "always", "never"];
+
+    /// Should output be styled in the current environment? In the browser,
+    /// output always goes to a terminal emulator that understands ANSI codes,
+    /// so auto mode always enables styling there.
+    pub fn is_enabled(self) -> bool {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            use std::io::IsTerminal;
+
+            // https://no-color.org/
+            let no_color =
+                std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            self.resolve(no_color, std::io::stdout().is_terminal())
+        }
+        #[cfg(target_family = "wasm")]
+        {
+            self.resolve(false, true)
+        }
+    }
+
+    /// Decide whether to style output, given the state of the environment
+    fn resolve(self, no_color: bool, is_terminal: bool) -> bool {
+        match self {
+            Self::Auto => !no_color && is_terminal,
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = OsrsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => {
+                Err(OsrsError::ArgsError(format!("Unknown color mode: {}", s)))
+            }
+        }
+    }
+}
+
+/// Write a value as JSON, followed by a newline
+fn write_json_line<T: Serialize + ?Sized>(
+    mut output: impl Write,
+    value: &T,
+    pretty: bool,
+) -> anyhow::Result<()> {
+    if pretty {
+        serde_json::to_writer_pretty(&mut output, value)?;
+    } else {
+        serde_json::to_writer(&mut output, value)?;
+    }
+    output.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Record {
+        name: &'static str,
+        value: Option<usize>,
+    }
+
+    const RECORDS: &[Record] = &[
+        Record {
+            name: "Shark",
+            value: Some(1000),
+        },
+        Record {
+            name: "Lobster",
+            value: None,
+        },
+    ];
+
+    fn render(format: OutputFormat) -> String {
+        let mut output = Vec::new();
+        format.write_records(&mut output, RECORDS).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("CSV".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_color_mode() {
+        assert_eq!("NEVER".parse::<ColorMode>().unwrap(), ColorMode::Never);
+        assert!("sometimes".parse::<ColorMode>().is_err());
+
+        assert!(ColorMode::Auto.resolve(false, true));
+        assert!(!ColorMode::Auto.resolve(true, true));
+        assert!(!ColorMode::Auto.resolve(false, false));
+        assert!(ColorMode::Always.resolve(true, false));
+        assert!(!ColorMode::Never.resolve(false, true));
+    }
+
+    #[test]
+    fn test_write_records() {
+        assert_eq!(
+            render(OutputFormat::Ndjson),
+            "{\"name\":\"Shark\",\"value\":1000}\n\
+            {\"name\":\"Lobster\",\"value\":null}\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Csv),
+            "name,value\nShark,1000\nLobster,\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+        assert_eq!(json[0]["value"], 1000);
+        assert_eq!(json[1]["value"], serde_json::Value::Null);
+    }
+}
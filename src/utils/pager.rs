@@ -0,0 +1,353 @@
+//! Paging for long output. Output is held back until it's too long to fit on
+//! the screen, and only then sent through the user's pager. Short output is
+//! written straight to stdout, so it behaves the same as without a pager.
+
+use crate::utils::{terminal, trace};
+use std::{
+    env,
+    io::{self, Stdout, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// Pager to use if `$PAGER` isn't set
+const DEFAULT_PAGER: &str = "less";
+
+/// A writer that pages long output. Output goes to stdout, unless it's
+/// replaced for tests.
+pub struct Pager<W: Write = Stdout> {
+    state: PagerState,
+    /// Where output goes if it isn't paged
+    output: W,
+    /// Number of lines that fit on the screen
+    height: usize,
+    /// Start the pager. This is only called once, when output gets too long.
+    spawn: Box<SpawnPager>,
+}
+
+/// Start a pager. Returns `None` if there's no usable pager.
+type SpawnPager = dyn FnMut() -> Option<Box<dyn PagerProcess>>;
+
+enum PagerState {
+    /// Output is short so far, hold onto it
+    Buffering { buffer: Vec<u8>, lines: usize },
+    /// Output is being sent to the pager
+    Paging(Box<dyn PagerProcess>),
+    /// The pager quit before all output was written. The rest is discarded.
+    Closed(Box<dyn PagerProcess>),
+    /// There's no usable pager, so everything goes straight to the output
+    Direct,
+}
+
+/// A running pager, which output can be sent to
+trait PagerProcess {
+    /// The pager's input
+    fn input(&mut self) -> &mut dyn Write;
+
+    /// Close the pager's input so it knows the output is done, and wait for
+    /// the user to exit it
+    fn wait(self: Box<Self>) -> io::Result<()>;
+}
+
+impl Pager {
+    /// Create a pager for stdout. Returns `None` if stdout isn't a terminal.
+    pub fn new() -> Option<Self> {
+        let height = terminal::height()?;
+        // Leave room for the shell prompt after the output
+        let height = usize::from(height).saturating_sub(1);
+        Some(Self::with_output(
+            io::stdout(),
+            height,
+            Box::new(spawn_pager),
+        ))
+    }
+}
+
+impl<W: Write> Pager<W> {
+    /// Create a pager with the given output, screen height and way of
+    /// starting the pager
+    fn with_output(output: W, height: usize, spawn: Box<SpawnPager>) -> Self {
+        Self {
+            state: PagerState::Buffering {
+                buffer: Vec::new(),
+                lines: 0,
+            },
+            output,
+            height,
+            spawn,
+        }
+    }
+
+    /// Write any held back output, and wait for the user to exit the pager
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.state {
+            PagerState::Buffering { buffer, .. } => {
+                self.output.write_all(&buffer)?;
+                self.output.flush()
+            }
+            PagerState::Paging(process) | PagerState::Closed(process) => {
+                process.wait()
+            }
+            PagerState::Direct => self.output.flush(),
+        }
+    }
+
+    /// Output is too long for the screen, so start the pager and send it
+    /// everything held back so far. If the pager can't be started, output
+    /// goes straight through instead.
+    fn start(&mut self, buffer: &[u8]) -> io::Result<()> {
+        self.state = match (self.spawn)() {
+            Some(process) => PagerState::Paging(process),
+            None => PagerState::Direct,
+        };
+        self.write_through(buffer)
+    }
+
+    /// Write directly to wherever output is going, without buffering
+    fn write_through(&mut self, data: &[u8]) -> io::Result<()> {
+        match &mut self.state {
+            PagerState::Buffering { .. } => {
+                unreachable!("Pager output is still buffered")
+            }
+            PagerState::Paging(process) => {
+                let result = process.input().write_all(data);
+                self.check_closed(result)
+            }
+            PagerState::Closed(_) => Ok(()),
+            PagerState::Direct => self.output.write_all(data),
+        }
+    }
+
+    /// If writing to the pager failed because the user quit it early, stop
+    /// sending output to it. That isn't an error.
+    fn check_closed(&mut self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {
+                let state =
+                    std::mem::replace(&mut self.state, PagerState::Direct);
+                if let PagerState::Paging(process) = state {
+                    self.state = PagerState::Closed(process);
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+impl<W: Write> Write for Pager<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match &mut self.state {
+            PagerState::Buffering { buffer, lines } => {
+                buffer.extend_from_slice(data);
+                *lines += data.iter().filter(|b| **b == b'\n').count();
+                if *lines > self.height {
+                    let buffer = std::mem::take(buffer);
+                    self.start(&buffer)?;
+                }
+            }
+            _ => self.write_through(data)?,
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.state {
+            // Held back output stays that way until we know how long it is
+            PagerState::Buffering { .. } | PagerState::Closed(_) => Ok(()),
+            PagerState::Paging(process) => {
+                let result = process.input().flush();
+                self.check_closed(result)
+            }
+            PagerState::Direct => self.output.flush(),
+        }
+    }
+}
+
+/// A pager running as a child process
+struct ChildPager {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+impl PagerProcess for ChildPager {
+    fn input(&mut self) -> &mut dyn Write {
+        &mut self.stdin
+    }
+
+    fn wait(self: Box<Self>) -> io::Result<()> {
+        let Self { mut child, stdin } = *self;
+        drop(stdin);
+        child.wait()?;
+        Ok(())
+    }
+}
+
+/// Start the pager from `$PAGER`, falling back to `less`. Returns `None` if
+/// paging is disabled (`PAGER` is empty or `cat`) or the pager couldn't be
+/// started.
+fn spawn_pager() -> Option<Box<dyn PagerProcess>> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.into());
+    let words = match shell_words::split(&pager) {
+        Ok(words) => words,
+        Err(error) => {
+            trace::trace(format_args!("Invalid PAGER `{}`: {}", pager, error));
+            return None;
+        }
+    };
+    let (program, args) = words.split_first()?;
+    if program == "cat" {
+        return None;
+    }
+
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::piped());
+    // Same as git: keep colors, and don't clear the screen on exit
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    match command.spawn() {
+        Ok(mut child) => {
+            trace::trace(format_args!("Paging output with `{}`", pager));
+            // We asked for a piped stdin, so it's always there
+            let stdin = child.stdin.take().unwrap();
+            Some(Box::new(ChildPager { child, stdin }))
+        }
+        Err(error) => {
+            trace::trace(format_args!(
+                "Error starting pager `{}`: {}",
+                pager, error
+            ));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// What happened to a fake pager
+    #[derive(Debug, Default)]
+    struct FakeState {
+        spawned: bool,
+        input: Vec<u8>,
+        waited: bool,
+    }
+
+    /// A pager that records its input. If it has a capacity, it quits once
+    /// that much input is written, like a user quitting `less` early.
+    struct FakePager {
+        state: Rc<RefCell<FakeState>>,
+        capacity: Option<usize>,
+    }
+
+    impl PagerProcess for FakePager {
+        fn input(&mut self) -> &mut dyn Write {
+            self
+        }
+
+        fn wait(self: Box<Self>) -> io::Result<()> {
+            self.state.borrow_mut().waited = true;
+            Ok(())
+        }
+    }
+
+    impl Write for FakePager {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            let mut state = self.state.borrow_mut();
+            if let Some(capacity) = self.capacity {
+                if state.input.len() + data.len() > capacity {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+            }
+            state.input.extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Create a pager for a 3-line screen, with a fake pager process
+    fn pager(
+        output: &mut Vec<u8>,
+        capacity: Option<usize>,
+    ) -> (Pager<&mut Vec<u8>>, Rc<RefCell<FakeState>>) {
+        let state = Rc::new(RefCell::new(FakeState::default()));
+        let spawn_state = Rc::clone(&state);
+        let pager = Pager::with_output(
+            output,
+            3,
+            Box::new(move || {
+                spawn_state.borrow_mut().spawned = true;
+                Some(Box::new(FakePager {
+                    state: Rc::clone(&spawn_state),
+                    capacity,
+                }))
+            }),
+        );
+        (pager, state)
+    }
+
+    /// Output that fits on the screen is held back, then written directly
+    #[test]
+    fn test_short_output() {
+        let mut output = Vec::new();
+        let (mut pager, state) = pager(&mut output, None);
+        pager.write_all(b"one\ntwo\n").unwrap();
+        pager.write_all(b"three\n").unwrap();
+        pager.flush().unwrap();
+        pager.finish().unwrap();
+        assert!(!state.borrow().spawned);
+        assert_eq!(output, b"one\ntwo\nthree\n");
+    }
+
+    /// Once output is too long for the screen, all of it goes to the pager
+    #[test]
+    fn test_long_output() {
+        let mut output = Vec::new();
+        let (mut pager, state) = pager(&mut output, None);
+        pager.write_all(b"one\ntwo\nthree\n").unwrap();
+        assert!(!state.borrow().spawned);
+        pager.write_all(b"four\n").unwrap();
+        assert!(state.borrow().spawned);
+        pager.write_all(b"five\n").unwrap();
+        pager.finish().unwrap();
+
+        let state = state.borrow();
+        assert_eq!(state.input, b"one\ntwo\nthree\nfour\nfive\n");
+        assert!(state.waited);
+        assert_eq!(output, b"");
+    }
+
+    /// If there's no usable pager, long output is written directly
+    #[test]
+    fn test_no_pager() {
+        let mut output = Vec::new();
+        let mut pager = Pager::with_output(&mut output, 1, Box::new(|| None));
+        pager.write_all(b"one\ntwo\n").unwrap();
+        pager.write_all(b"three\n").unwrap();
+        pager.finish().unwrap();
+        assert_eq!(output, b"one\ntwo\nthree\n");
+    }
+
+    /// If the user quits the pager early, the rest of the output is dropped
+    /// without an error
+    #[test]
+    fn test_pager_closed() {
+        let mut output = Vec::new();
+        let (mut pager, state) = pager(&mut output, Some(20));
+        pager.write_all(b"one\ntwo\nthree\nfour\n").unwrap();
+        pager.write_all(b"five\n").unwrap();
+        pager.write_all(b"six\n").unwrap();
+        pager.flush().unwrap();
+        pager.finish().unwrap();
+
+        let state = state.borrow();
+        assert_eq!(state.input, b"one\ntwo\nthree\nfour\n");
+        assert!(state.waited);
+        assert_eq!(output, b"");
+    }
+}
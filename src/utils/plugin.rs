@@ -0,0 +1,196 @@
+//! External subcommands. If a command isn't built in or an alias, we look for
+//! an executable named `osrs-<name>` on the `PATH` and run it, the same way git
+//! and cargo do. This lets anyone add commands without changing this crate.
+
+use crate::{
+    config::OsrsConfig, error::OsrsError, utils::context::CommandContext,
+};
+use anyhow::Context;
+use std::{
+    env::{self, consts::EXE_SUFFIX},
+    ffi::OsStr,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Prefix of every plugin's executable name
+const PLUGIN_PREFIX: &str = "osrs-";
+
+/// Find the executable for a plugin on the `PATH`
+pub fn find(name: &str) -> Option<PathBuf> {
+    find_in(name, &env::var_os("PATH")?)
+}
+
+/// Find the executable for a plugin in a `PATH`-style list of directories
+fn find_in(name: &str, path: &OsStr) -> Option<PathBuf> {
+    // A name with a path separator could point outside the PATH directories
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    let file_name = format!("{}{}{}", PLUGIN_PREFIX, name, EXE_SUFFIX);
+    env::split_paths(path)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+/// Run a plugin with the given args. Its stdout is copied to the command
+/// output, so it can be paged, watched or sent as a notification like the
+/// output of any other command. The resolved config and options are passed in
+/// environment variables (see [env_vars]).
+pub fn run<O: Write>(
+    name: &str,
+    executable: &Path,
+    args: &[String],
+    context: &mut CommandContext<O>,
+) -> anyhow::Result<()> {
+    let mut child = Command::new(executable)
+        .args(args)
+        .envs(env_vars(context)?)
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| {
+            format!("Error running plugin `{}`", executable.display())
+        })?;
+    // We asked for a piped stdout, so it's always there
+    let mut stdout = child.stdout.take().unwrap();
+    io::copy(&mut stdout, &mut context.output)?;
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(OsrsError::PluginFailed {
+            name: format!("{}{}", PLUGIN_PREFIX, name),
+            // No code means the plugin was killed by a signal
+            exit_code: status.code().unwrap_or(OsrsError::UNKNOWN_EXIT_CODE),
+        }
+        .into())
+    }
+}
+
+/// Environment variables that tell a plugin how it was run. Config values use
+/// the same names as config overrides, so if the plugin runs `osrs` itself, it
+/// gets the same player and profile. The format and color aren't config, so
+/// the plugin has to pass them along with `--format` and `--color`.
+fn env_vars<O: Write>(
+    context: &CommandContext<O>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let config = context.config();
+    let prefixed = |name: &str| format!("{}{}", OsrsConfig::ENV_PREFIX, name);
+    let account_type = serde_json::to_value(config.account_type)?;
+    let mut vars = vec![
+        (
+            prefixed("ACCOUNT_TYPE"),
+            account_type.as_str().unwrap_or_default().to_owned(),
+        ),
+        (prefixed("FORMAT"), context.format().to_string()),
+        (
+            prefixed("COLOR"),
+            if context.color() { "always" } else { "never" }.to_owned(),
+        ),
+    ];
+    if let Some(player) = &config.default_player {
+        vars.push((prefixed("DEFAULT_PLAYER"), player.clone()));
+    }
+    if let Some(profile) = &config.active_profile {
+        vars.push((prefixed("ACTIVE_PROFILE"), profile.clone()));
+    }
+    // Output goes through a pipe, so the plugin can't ask the terminal
+    if let Some(width) = context.width {
+        vars.push(("COLUMNS".into(), width.to_string()));
+    }
+    // So the plugin can call back into this binary, like cargo's `CARGO`
+    if let Ok(exe) = env::current_exe() {
+        vars.push(("OSRS".into(), exe.to_string_lossy().into_owned()));
+    }
+    Ok(vars)
+}
+
+/// Can we run the file at this path?
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.metadata()
+            .map(|metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            })
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::utils::output::OutputFormat;
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    /// Create a directory with a plugin script in it
+    fn plugin_dir(test_name: &str, name: &str, script: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "osrs-plugin-{}-{}",
+            test_name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("osrs-{}", name));
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_find() {
+        let dir = plugin_dir("find", "hello", "true");
+        fs::write(dir.join("osrs-data"), "not executable").unwrap();
+        let path = env::join_paths(["/does/not/exist".as_ref(), dir.as_path()])
+            .unwrap();
+
+        assert_eq!(find_in("hello", &path), Some(dir.join("osrs-hello")));
+        assert_eq!(find_in("data", &path), None);
+        assert_eq!(find_in("missing", &path), None);
+        assert_eq!(find_in("", &path), None);
+        assert_eq!(find_in("../osrs-hello", &path), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = plugin_dir(
+            "run",
+            "hello",
+            r#"echo "$@ $OSRS_DEFAULT_PLAYER $OSRS_ACCOUNT_TYPE $OSRS_FORMAT"
+            exit $1"#,
+        );
+        let executable = dir.join("osrs-hello");
+        let mut output = Vec::new();
+        let mut context =
+            CommandContext::for_test(&mut output, OutputFormat::Json);
+        context.config.default_player = Some("Lynx Titan".into());
+
+        run(
+            "hello",
+            &executable,
+            &["0".into(), "a b".into()],
+            &mut context,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(context.output.as_slice()),
+            "0 a b Lynx Titan normal json\n"
+        );
+
+        // The plugin's exit code is passed through
+        let error =
+            run("hello", &executable, &["3".into()], &mut context).unwrap_err();
+        let report = crate::ErrorReport::from(&error);
+        assert_eq!(report.kind, "plugin_failed");
+        assert_eq!(report.exit_code, 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
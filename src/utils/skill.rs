@@ -1,12 +1,15 @@
 use crate::error::OsrsError;
-use serde::Deserialize;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
 /// A macro to reduce copy-pasta for defining the list of all skills
 macro_rules! skills {
     ($(($skill:ident, $aliases:expr)),+ $(,)?) => {
         /// One player skill (e.g. Attack, Woodcutting)
-        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+        #[derive(
+            Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize,
+        )]
         pub enum Skill {
             $(
                 $skill,
@@ -53,6 +56,25 @@ skills! {
     (Construction, &["con", "cons"]),
 }
 
+lazy_static! {
+    /// Every string that can be parsed as a skill: each full name, followed by
+    /// its aliases
+    static ref SKILL_NAMES: Vec<&'static str> = SKILLS_TO_NAMES
+        .iter()
+        .flat_map(|(_, primary_name, aliases)| {
+            std::iter::once(*primary_name).chain(aliases.iter().copied())
+        })
+        .collect();
+}
+
+impl Skill {
+    /// Get every string that can be parsed as a skill, including aliases.
+    /// Useful for CLI help and shell completions.
+    pub fn names() -> &'static [&'static str] {
+        &SKILL_NAMES
+    }
+}
+
 impl FromStr for Skill {
     type Err = OsrsError;
 
@@ -81,3 +103,20 @@ impl Display for Skill {
         panic!("Could not format name for skill: {self:?}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_names() {
+        let names = Skill::names();
+        assert_eq!(names[0], "Overall");
+        assert!(names.contains(&"Woodcutting"));
+        assert!(names.contains(&"wc"));
+        // Every name should parse back to a skill
+        for name in names {
+            assert!(name.parse::<Skill>().is_ok(), "{} didn't parse", name);
+        }
+    }
+}
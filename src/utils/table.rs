@@ -1,6 +1,122 @@
-use comfy_table::{Cell, CellAlignment, Table};
+#[cfg(feature = "cli")]
+use comfy_table::{presets, Cell, CellAlignment, Table};
+use serde::{Deserialize, Serialize};
+
+/// Max length of a Discord message, in characters
+#[cfg(feature = "cli")]
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+/// Fence for a Discord code block
+#[cfg(feature = "cli")]
+pub const CODE_FENCE: &str = "```";
+
+/// How tables in text output are drawn
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TableStyle {
+    /// ASCII borders
+    #[default]
+    Ascii,
+    /// Unicode box drawing characters
+    Unicode,
+    /// Aligned columns with no lines at all
+    Borderless,
+    /// A Markdown table
+    Markdown,
+    /// ASCII borders, wrapped in a code block. Long tables are split into
+    /// multiple code blocks that each fit in one Discord message.
+    Discord,
+}
+
+#[cfg(feature = "cli")]
+impl TableStyle {
+    /// The comfy-table preset that draws this style
+    fn preset(self) -> &'static str {
+        match self {
+            Self::Ascii | Self::Discord => {
+                presets::ASCII_BORDERS_ONLY_CONDENSED
+            }
+            Self::Unicode => presets::UTF8_BORDERS_ONLY,
+            Self::Borderless => presets::NOTHING,
+            Self::Markdown => presets::ASCII_MARKDOWN,
+        }
+    }
+
+    /// Should tables in this style be wrapped to fit the terminal? Styles
+    /// meant for pasting elsewhere shouldn't be, since wrapped cells would
+    /// break Markdown rows.
+    pub fn fits_terminal(self) -> bool {
+        match self {
+            Self::Ascii | Self::Unicode | Self::Borderless => true,
+            Self::Markdown | Self::Discord => false,
+        }
+    }
+
+    /// Draw a table in this style. The result is split into one or more
+    /// blocks of text, which should be printed separately. Only Discord
+    /// tables are ever split.
+    pub fn render(self, table: &mut Table) -> Vec<String> {
+        table.load_preset(self.preset());
+        let rendered = table.to_string();
+        match self {
+            Self::Discord => discord_code_blocks(&rendered),
+            _ => vec![rendered],
+        }
+    }
+}
+
+/// Wrap text in one or more code blocks, each of which fits in a single
+/// Discord message
+#[cfg(feature = "cli")]
+pub fn discord_code_blocks(text: &str) -> Vec<String> {
+    split_lines(text, DISCORD_MESSAGE_LIMIT)
+        .into_iter()
+        .map(|chunk| format!("{CODE_FENCE}\n{chunk}\n{CODE_FENCE}"))
+        .collect()
+}
+
+/// Split text into chunks that each fit into a code block of at most `limit`
+/// characters (including the fences). Chunks are split on line boundaries,
+/// unless a single line is too long on its own.
+#[cfg(feature = "cli")]
+fn split_lines(text: &str, limit: usize) -> Vec<String> {
+    // Leave room for the opening and closing fences, each with a newline
+    let limit = limit - 2 * (CODE_FENCE.len() + 1);
+    // Any line that's too long on its own gets cut into multiple lines
+    let lines = text.lines().flat_map(|line| {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            vec![String::new()]
+        } else {
+            chars
+                .chunks(limit)
+                .map(|piece| piece.iter().collect())
+                .collect()
+        }
+    });
+
+    let mut chunks: Vec<Vec<String>> = Vec::new();
+    let mut chunk_len = 0;
+    for line in lines {
+        let line_len = line.chars().count();
+        match chunks.last_mut() {
+            // +1 for the newline before this line
+            Some(chunk) if chunk_len + 1 + line_len <= limit => {
+                chunk.push(line);
+                chunk_len += 1 + line_len;
+            }
+            _ => {
+                chunks.push(vec![line]);
+                chunk_len = line_len;
+            }
+        }
+    }
+    chunks.into_iter().map(|chunk| chunk.join("\n")).collect()
+}
 
 /// Additional functionality for the [Table](comfy_table::Table) type
+#[cfg(feature = "cli")]
 pub trait TableExt {
     /// Set the headers of a table in conjunction with an alignment for each
     /// column
@@ -10,6 +126,7 @@ pub trait TableExt {
     ) -> &mut Self;
 }
 
+#[cfg(feature = "cli")]
 impl TableExt for Table {
     fn set_aligned_header<T: Into<Cell>>(
         &mut self,
@@ -19,10 +136,96 @@ impl TableExt for Table {
             headers.into_iter().unzip();
         self.set_header(header);
         for (i, alignment) in alignments.into_iter().enumerate() {
-            let column =
-                self.get_column_mut(i).expect("No column with index {i}");
+            let column = self.column_mut(i).expect("No column with index {i}");
             column.set_cell_alignment(alignment);
         }
         self
     }
 }
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::new();
+        table
+            .set_header(["Item", "Price"])
+            .add_row(["Shark", "1,000"]);
+        table
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            TableStyle::Ascii.render(&mut table()),
+            vec![
+                "\
++---------------+
+| Item    Price |
++===============+
+| Shark   1,000 |
++---------------+"
+            ]
+        );
+        assert_eq!(
+            TableStyle::Markdown.render(&mut table()),
+            vec![
+                "\
+| Item  | Price |
+|-------|-------|
+| Shark | 1,000 |"
+            ]
+        );
+        assert_eq!(
+            TableStyle::Discord.render(&mut table()),
+            vec![
+                "\
+```
++---------------+
+| Item    Price |
++===============+
+| Shark   1,000 |
++---------------+
+```"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_discord_split() {
+        let mut table = Table::new();
+        table.set_header(["Item", "Price"]);
+        for i in 0..200 {
+            table.add_row([format!("Item {i}"), i.to_string()]);
+        }
+        let chunks = TableStyle::Discord.render(&mut table);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= DISCORD_MESSAGE_LIMIT);
+            assert!(chunk.starts_with("```\n") && chunk.ends_with("\n```"));
+        }
+        // Nothing is lost in the split
+        let joined: Vec<&str> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.lines().filter(|line| *line != "```"))
+            .collect();
+        assert_eq!(joined.join("\n"), table.to_string());
+    }
+
+    #[test]
+    fn test_split_lines() {
+        // Limit of 20 leaves 12 characters for content
+        assert_eq!(split_lines("", 20), Vec::<String>::new());
+        assert_eq!(split_lines("abc\ndef", 20), vec!["abc\ndef"]);
+        assert_eq!(
+            split_lines("abcdef\nghijkl\nmn", 20),
+            vec!["abcdef", "ghijkl\nmn"]
+        );
+        // Long lines are cut
+        assert_eq!(
+            split_lines("abcdefghijklmnop\n\nq", 20),
+            vec!["abcdefghijkl", "mnop\n\nq"]
+        );
+    }
+}
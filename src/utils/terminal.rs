@@ -0,0 +1,57 @@
+//! Terminal size detection, used to fit text output to the screen. Natively,
+//! the size is read from the terminal that stdout is attached to. In the
+//! browser, there's no terminal to ask, so the website passes in its width.
+
+#[cfg(target_family = "wasm")]
+use std::sync::atomic::{AtomicU16, Ordering};
+
+/// Width of the website's terminal, in columns. 0 means unknown. This is set
+/// once per command by the JS entrypoint.
+#[cfg(target_family = "wasm")]
+static WIDTH: AtomicU16 = AtomicU16::new(0);
+
+/// Set the width of the browser terminal for all subsequent output
+#[cfg(target_family = "wasm")]
+pub fn set_width(width: Option<u16>) {
+    WIDTH.store(width.unwrap_or_default(), Ordering::Relaxed);
+}
+
+/// Get the width of the terminal, in columns. Returns `None` if stdout isn't
+/// a terminal, or the website didn't give a width.
+pub fn width() -> Option<u16> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        size().map(|(width, _)| width)
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        Some(WIDTH.load(Ordering::Relaxed)).filter(|width| *width > 0)
+    }
+}
+
+/// Get the height of the terminal, in rows. Returns `None` if stdout isn't a
+/// terminal.
+#[cfg(not(target_family = "wasm"))]
+pub fn height() -> Option<u16> {
+    size().map(|(_, height)| height)
+}
+
+/// Is stdout attached to a terminal?
+#[cfg(not(target_family = "wasm"))]
+pub fn is_terminal() -> bool {
+    use std::io::IsTerminal;
+
+    std::io::stdout().is_terminal()
+}
+
+/// Get the (width, height) of the terminal attached to stdout
+#[cfg(not(target_family = "wasm"))]
+fn size() -> Option<(u16, u16)> {
+    if is_terminal() {
+        crossterm::terminal::size()
+            .ok()
+            .filter(|(width, height)| *width > 0 && *height > 0)
+    } else {
+        None
+    }
+}
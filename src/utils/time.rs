@@ -0,0 +1,116 @@
+//! Utilities for dealing with time. The standard library's clocks and the
+//! async runtime's timers aren't available in wasm, so these have separate
+//! implementations for each platform.
+
+#[cfg(feature = "cli")]
+use crate::error::OsrsError;
+use std::time::Duration;
+
+/// Get the current time, in milliseconds since the Unix epoch
+#[cfg(not(target_family = "wasm"))]
+pub fn now_millis() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        // Clock is before 1970?? Sure, whatever
+        .unwrap_or_default()
+}
+
+/// Get the current time, in milliseconds since the Unix epoch. `SystemTime`
+/// isn't supported in wasm, so we have to ask JS.
+#[cfg(target_family = "wasm")]
+pub fn now_millis() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// Get the current time, in seconds since the Unix epoch
+pub fn now_secs() -> u64 {
+    now_millis() / 1000
+}
+
+/// Wait for the given amount of time, without blocking other tasks
+#[cfg(not(target_family = "wasm"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for the given amount of time, without blocking other tasks. This uses
+/// JS's `setTimeout`.
+#[cfg(target_family = "wasm")]
+pub async fn sleep(duration: Duration) {
+    use crate::utils::browser;
+    use wasm_bindgen::JsValue;
+
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = browser::window().ok().and_then(|window| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    &resolve,
+                    duration.as_millis() as i32,
+                )
+                .ok()
+        });
+        // If we can't set a timer, don't wait forever
+        if scheduled.is_none() {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Parse a duration like `30s`, `5m`, `1h` or `1m30s`. A plain number is a
+/// number of seconds. Zero durations are rejected.
+#[cfg(feature = "cli")]
+pub fn parse_duration(value: &str) -> Result<Duration, OsrsError> {
+    let invalid = || {
+        OsrsError::ArgsError(format!(
+            "Invalid duration `{}`. Use e.g. `30s`, `5m`, `1h` or `1m30s`",
+            value
+        ))
+    };
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs))
+            .filter(|duration| !duration.is_zero())
+            .ok_or_else(invalid);
+    }
+
+    let mut total = 0u64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = match rest[digits..].chars().next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        total = total.saturating_add(amount.saturating_mul(unit));
+        rest = &rest[digits + 1..];
+    }
+    Some(Duration::from_secs(total))
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(invalid)
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+        for value in ["", "0", "0s", "s", "5x", "m5", "1.5m", "-1s"] {
+            assert!(parse_duration(value).is_err(), "{}", value);
+        }
+    }
+}
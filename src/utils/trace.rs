@@ -0,0 +1,170 @@
+//! Optional tracing of what the program is doing under the hood, e.g. which
+//! HTTP requests are made and whether responses come from the cache. Tracing
+//! is enabled with the `--verbose` flag. Traces are written to stderr, or the
+//! browser console in wasm, so they never mix with command output.
+
+#[cfg(test)]
+use std::cell::RefCell;
+use std::{
+    fmt::Arguments,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Is tracing enabled? This is set once per command, based on CLI args. It's
+/// global so that any code can trace without plumbing.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    /// Traces written on this thread while they're being captured. This is
+    /// per thread rather than global because tests run in parallel.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Enable or disable tracing for all subsequent operations
+#[cfg(feature = "cli")]
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Is tracing enabled? Check this before doing any expensive work that's only
+/// needed for a trace.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Write a trace message, if tracing is enabled. Takes pre-built format
+/// arguments (via `format_args!`), so that the message is only formatted if
+/// it will actually be written.
+pub fn trace(message: Arguments<'_>) {
+    #[cfg(test)]
+    if CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(traces) => {
+            traces.push(message.to_string());
+            true
+        }
+        None => false,
+    }) {
+        return;
+    }
+
+    if !is_enabled() {
+        return;
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    eprintln!("[osrs] {}", message);
+    #[cfg(target_family = "wasm")]
+    web_sys::console::debug_1(&format!("[osrs] {}", message).into());
+}
+
+/// Capture the traces written while running a future on this thread, instead
+/// of writing them to stderr. Traces are always captured, even if tracing is
+/// disabled.
+#[cfg(all(test, feature = "cli", not(target_family = "wasm")))]
+pub async fn capture(
+    future: impl std::future::Future<Output = ()>,
+) -> Vec<String> {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    future.await;
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+#[cfg(all(test, feature = "cli", not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        cache::PersistentCache,
+        fmt,
+        http::{self, CachePolicy, HttpCache},
+        transport::{self, Transport},
+    };
+    use std::{fs, thread, time::Duration};
+
+    /// Requests replayed from fixtures are traced with their URL and size
+    #[tokio::test]
+    async fn test_trace_replay() {
+        transport::replay_test_fixtures();
+        let url = "https://prices.runescape.wiki/api/v1/osrs/mapping";
+        let size = fs::metadata(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_data/http/prices.runescape.wiki_api_v1_osrs_mapping.json"
+        ))
+        .unwrap()
+        .len();
+
+        let traces = capture(async {
+            http::get::<serde_json::Value>(url, &[]).await.unwrap();
+        })
+        .await;
+        assert_eq!(traces.len(), 2, "{:#?}", traces);
+        assert!(
+            traces[0].starts_with(&format!("Replaying {} from `", url)),
+            "{}",
+            traces[0]
+        );
+        assert!(
+            traces[1].starts_with(&format!(
+                "GET {} returned {} bytes in ",
+                url,
+                fmt::fmt_int(&size)
+            )),
+            "{}",
+            traces[1]
+        );
+    }
+
+    /// Requests over the network are traced with their status, and cache
+    /// hits with where the data came from
+    #[tokio::test]
+    async fn test_trace_cache() {
+        let dir = PersistentCache::use_test_dir("trace_cache");
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data", server.server_addr());
+        let responder = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            request
+                .respond(tiny_http::Response::from_string("[1,2,3]"))
+                .unwrap();
+        });
+        let cache: HttpCache<Vec<u32>> =
+            HttpCache::new("test_trace", Duration::from_secs(60));
+        // A separate instance has nothing in memory, so it has to use the
+        // persistent cache
+        let other_cache: HttpCache<Vec<u32>> =
+            HttpCache::new("test_trace", Duration::from_secs(60));
+
+        let mut traces = capture(async {
+            let network = Transport::Network;
+            cache
+                .load_with(&url, CachePolicy::Refresh, &network)
+                .await
+                .unwrap();
+            cache
+                .load_with(&url, CachePolicy::Normal, &network)
+                .await
+                .unwrap();
+            other_cache
+                .load_with(&url, CachePolicy::Normal, &network)
+                .await
+                .unwrap();
+        })
+        .await;
+        responder.join().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        // Other tests make requests to the same host, so this one may have to
+        // wait its turn
+        traces.retain(|trace| !trace.starts_with("Waiting "));
+
+        assert_eq!(traces.len(), 5, "{:#?}", traces);
+        assert_eq!(traces[0], "Ignoring cached test_trace (refresh)");
+        assert_eq!(traces[1], format!("{} responded with 200 OK", url));
+        assert!(
+            traces[2].starts_with(&format!("GET {} returned 7 bytes in ", url)),
+            "{}",
+            traces[2]
+        );
+        assert_eq!(traces[3], "Using test_trace from memory");
+        assert_eq!(traces[4], "Using cached test_trace (fetched 0s ago)");
+    }
+}
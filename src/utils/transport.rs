@@ -0,0 +1,208 @@
+//! Pluggable sources for HTTP responses. Normally every request goes over the
+//! network, but (natively) requests can also be recorded to or replayed from
+//! a directory of fixture files. This makes it possible to test commands
+//! deterministically, or run them without an internet connection.
+
+use crate::{config::OsrsConfig, error::OsrsError, utils::http};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// The transport used for all HTTP requests. This is set once per command,
+/// based on config. It's global for the same reason as the cache policy: so we
+/// don't have to plumb it everywhere.
+static TRANSPORT: RwLock<Transport> = RwLock::new(Transport::Network);
+
+/// User-facing setting for where HTTP responses come from
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpMode {
+    /// Make real requests over the network
+    #[default]
+    Live,
+    /// Make real requests, and save each response to the fixture directory
+    Record,
+    /// Serve responses from the fixture directory, never touching the
+    /// network
+    Replay,
+}
+
+/// Where HTTP responses come from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transport {
+    /// Make real requests over the network
+    Network,
+    /// Make real requests, and save each response body to a file in the
+    /// given directory
+    #[cfg(not(target_family = "wasm"))]
+    Record(PathBuf),
+    /// Serve each response from a file in the given directory. Missing files
+    /// are an error.
+    #[cfg(not(target_family = "wasm"))]
+    Replay(PathBuf),
+}
+
+impl Transport {
+    /// Build a transport from config. Like any other config field, the mode
+    /// and fixture directory can be overridden with the `OSRS_HTTP_MODE` and
+    /// `OSRS_HTTP_FIXTURES` environment variables.
+    pub fn from_config(config: &OsrsConfig) -> anyhow::Result<Self> {
+        let mode = config.http_mode;
+        match (mode, config.http_fixtures.clone()) {
+            (HttpMode::Live, _) => Ok(Self::Network),
+            #[cfg(not(target_family = "wasm"))]
+            (HttpMode::Record, Some(dir)) => Ok(Self::Record(dir)),
+            #[cfg(not(target_family = "wasm"))]
+            (HttpMode::Replay, Some(dir)) => Ok(Self::Replay(dir)),
+            #[cfg(not(target_family = "wasm"))]
+            (_, None) => Err(OsrsError::ArgsError(format!(
+                "HTTP mode `{:?}` requires a fixture directory. \
+                Set `http_fixtures` in the config or {}HTTP_FIXTURES",
+                mode,
+                OsrsConfig::ENV_PREFIX
+            ))
+            .into()),
+            #[cfg(target_family = "wasm")]
+            (_, _) => Err(OsrsError::UnsupportedEnvironment(
+                "HTTP recording and replaying are not supported in the browser"
+                    .into(),
+            )
+            .into()),
+        }
+    }
+
+    /// Get the response body for a GET request to the given URL
+    pub async fn get(&self, url: &Url) -> anyhow::Result<String> {
+        match self {
+            Self::Network => http::fetch(url).await,
+
+            #[cfg(not(target_family = "wasm"))]
+            Self::Record(dir) => {
+                use crate::utils::trace::trace;
+                use anyhow::Context;
+                use std::fs;
+
+                let body = http::fetch(url).await?;
+                let path = fixture_path(dir, url);
+                fs::create_dir_all(dir)
+                    .and_then(|()| fs::write(&path, &body))
+                    .with_context(|| {
+                        format!(
+                            "Error recording response to `{}`",
+                            path.display()
+                        )
+                    })?;
+                trace(format_args!("Recorded {} to `{}`", url, path.display()));
+                Ok(body)
+            }
+
+            #[cfg(not(target_family = "wasm"))]
+            Self::Replay(dir) => {
+                use crate::utils::trace::trace;
+                use anyhow::Context;
+
+                let path = fixture_path(dir, url);
+                trace(format_args!(
+                    "Replaying {} from `{}`",
+                    url,
+                    path.display()
+                ));
+                std::fs::read_to_string(&path).with_context(|| {
+                    format!(
+                        "No recorded response for `{}` (expected file `{}`)",
+                        url,
+                        path.display()
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Set the transport for all subsequent requests
+pub fn set_transport(transport: Transport) {
+    // Lock can only be poisoned by a panic while setting, which can't happen
+    *TRANSPORT.write().unwrap() = transport;
+}
+
+/// Get the current transport. This returns a clone so that the lock isn't
+/// held while a request is in flight.
+pub fn transport() -> Transport {
+    TRANSPORT.read().unwrap().clone()
+}
+
+/// Replay responses from the fixtures that are checked into the repo, for
+/// deterministic tests of commands that make HTTP requests
+#[cfg(all(test, feature = "cli", not(target_family = "wasm")))]
+pub fn replay_test_fixtures() {
+    set_transport(Transport::Replay(
+        [env!("CARGO_MANIFEST_DIR"), "test_data", "http"]
+            .iter()
+            .collect(),
+    ));
+}
+
+/// Get the path to the fixture file for a URL. The file name is derived from
+/// everything after the scheme (host, port, path and query), with all special
+/// characters replaced so it's still readable.
+#[cfg(not(target_family = "wasm"))]
+fn fixture_path(dir: &std::path::Path, url: &Url) -> PathBuf {
+    let raw = url
+        .as_str()
+        .trim_start_matches(url.scheme())
+        .trim_start_matches("://");
+    let name: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.json", name))
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_fixture_path() {
+        let dir = Path::new("fixtures");
+        assert_eq!(
+            fixture_path(
+                dir,
+                &Url::parse("https://prices.runescape.wiki/api/v1/osrs/latest")
+                    .unwrap()
+            ),
+            dir.join("prices.runescape.wiki_api_v1_osrs_latest.json")
+        );
+        assert_eq!(
+            fixture_path(
+                dir,
+                &Url::parse("http://localhost:8000/latest").unwrap()
+            ),
+            dir.join("localhost_8000_latest.json")
+        );
+        assert_eq!(
+            fixture_path(
+                dir,
+                &Url::parse_with_params(
+                    "https://secure.runescape.com/m=hiscore_oldschool/index_lite.json",
+                    &[("player", "Lynx Titan")]
+                )
+                .unwrap()
+            ),
+            dir.join(
+                "secure.runescape.com_m_hiscore_oldschool_index_lite.json_player_Lynx_Titan.json"
+            )
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Helpers for watch mode, which re-runs a command on an interval and redraws
+//! its output, highlighting whatever changed since the previous run
+
+use crate::utils::fmt;
+use colored::Colorize;
+use crossterm::{
+    cursor::MoveTo,
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use std::{io::Write, time::Duration};
+
+/// Clear the screen and draw the header for one run of the command
+pub fn draw_header(
+    output: &mut impl Write,
+    interval: Duration,
+    run: usize,
+    clear: bool,
+) -> anyhow::Result<()> {
+    if clear {
+        output.queue(Clear(ClearType::All))?.queue(MoveTo(0, 0))?;
+    }
+    let header = format!(
+        "Every {} (run {}). Press Ctrl-C to stop.",
+        fmt::fmt_duration(interval),
+        run
+    );
+    writeln!(output, "{}\n", header.dimmed())?;
+    Ok(())
+}
+
+/// Highlight everything in `current` output that's different from the same
+/// spot in `previous` output. Output is compared line by line, and each line
+/// word by word, so a changed table cell is highlighted without the rest of
+/// its row. `highlight` applies the styling to one changed word.
+pub fn highlight_changes(
+    previous: &str,
+    current: &str,
+    highlight: impl Fn(&str) -> String,
+) -> String {
+    let previous_lines: Vec<&str> = previous.lines().collect();
+    let mut output = String::with_capacity(current.len());
+    for (i, line) in current.lines().enumerate() {
+        let previous_line = previous_lines.get(i).copied().unwrap_or_default();
+        if line == previous_line {
+            output.push_str(line);
+        } else {
+            let previous_words = words(previous_line);
+            for (j, word) in words(line).into_iter().enumerate() {
+                // Changes in spacing are just from columns being resized
+                if word.trim().is_empty()
+                    || previous_words.get(j) == Some(&word)
+                {
+                    output.push_str(word);
+                } else {
+                    output.push_str(&highlight(word));
+                }
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Split a line into words and the whitespace between them. Whitespace is
+/// kept, so the pieces can be joined back into the original line.
+fn words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let is_space = c.is_whitespace();
+        match chars.peek() {
+            Some((next_index, next)) if next.is_whitespace() != is_space => {
+                words.push(&line[start..*next_index]);
+                start = *next_index;
+            }
+            Some(_) => {}
+            None => words.push(&line[start..]),
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        cache::{CacheEntry, PersistentCache},
+        context::GlobalOptions,
+        http::{CachePolicy, HttpCache},
+        transport::Transport,
+    };
+    use std::{sync::Arc, thread};
+
+    /// Runs after the first fetch data again, even if there's a fresh copy
+    /// in the persistent cache
+    #[tokio::test]
+    async fn test_watch_refetch() {
+        let dir = PersistentCache::use_test_dir("watch_refetch");
+        let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+        let url = format!("http://{}/latest", server.server_addr());
+        let responder = thread::spawn({
+            let server = Arc::clone(&server);
+            move || {
+                let mut requests = 0;
+                for request in server.incoming_requests() {
+                    requests += 1;
+                    let response = tiny_http::Response::from_string("2");
+                    request.respond(response).unwrap();
+                }
+                requests
+            }
+        });
+        let key = HttpCache::<u32>::new("test_watch", Duration::from_secs(60))
+            .persistent_key(&url);
+        PersistentCache::set(&key, &CacheEntry::new("1".into())).unwrap();
+
+        let options = GlobalOptions::default();
+        let mut values = Vec::new();
+        for run in 1..=2 {
+            // A new cache for each run, same as clearing the memory caches
+            let cache: HttpCache<u32> =
+                HttpCache::new("test_watch", Duration::from_secs(60));
+            let policy = options.for_watch_run(run).cache_policy();
+            let value = cache
+                .load_with(&url, policy, &Transport::Network)
+                .await
+                .map(|value| *value);
+            values.push(value);
+        }
+        server.unblock();
+        let requests = responder.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let values: Vec<u32> = values.into_iter().map(Result::unwrap).collect();
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!(requests, 1);
+    }
+
+    /// Runs after the first refresh data, but offline mode still wins
+    #[test]
+    fn test_watch_cache_policy() {
+        let options = GlobalOptions {
+            offline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.for_watch_run(1).cache_policy(),
+            CachePolicy::Offline
+        );
+        assert_eq!(
+            options.for_watch_run(2).cache_policy(),
+            CachePolicy::Offline
+        );
+        assert_eq!(
+            GlobalOptions::default().for_watch_run(2).cache_policy(),
+            CachePolicy::Refresh
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words(""), Vec::<&str>::new());
+        assert_eq!(
+            words("| Shark   1,000 |"),
+            vec!["|", " ", "Shark", "   ", "1,000", " ", "|"]
+        );
+        assert_eq!(words("  a"), vec!["  ", "a"]);
+    }
+
+    #[test]
+    fn test_highlight_changes() {
+        let highlight = |word: &str| format!("[{}]", word);
+        let previous = "\
+| Shark       1,000 |
+| Raw shark     850 |
+";
+        // Nothing changed
+        assert_eq!(highlight_changes(previous, previous, highlight), previous);
+        assert_eq!(
+            highlight_changes(
+                previous,
+                "\
+| Shark       1,050 |
+| Raw shark     850 |
+| Tuna          100 |
+",
+                highlight
+            ),
+            "\
+| Shark       [1,050] |
+| Raw shark     850 |
+[|] [Tuna]          [100] [|]
+"
+        );
+        // Column widths changing doesn't count as a change
+        assert_eq!(
+            highlight_changes("| a  1 |", "| a 1 |", highlight),
+            "| a 1 |\n"
+        );
+    }
+}
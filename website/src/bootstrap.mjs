@@ -1,4 +1,4 @@
-const { runCommand } = await import("osrs-cli");
+const { runCommandStreaming } = await import("osrs-cli");
 import { Terminal } from "xterm";
 import { XtermShell } from "xterm-shell";
 import { FitAddon } from "xterm-addon-fit";
@@ -12,13 +12,28 @@ const terminal = new Terminal({
 const fitAddon = new FitAddon();
 terminal.loadAddon(fitAddon);
 
+// Width of the terminal in columns, so tables can be wrapped to fit. This is
+// kept up to date as the terminal is resized.
+let width = terminal.cols;
+terminal.onResize(({ cols }) => {
+  width = cols;
+});
+
 const shell = new XtermShell(terminal);
 shell
   .setPrompt("> osrs ")
   .addGlobalCommandHandler(async (shell, command, args) => {
-    // `command` will be the first arg, i.e. the osrs subcommand
-    const output = await runCommand(["osrs", command, ...args]);
-    shell.print(output);
+    // `command` will be the first arg, i.e. the osrs subcommand. Output is
+    // printed as it comes in, so slow commands show progress.
+    try {
+      await runCommandStreaming(
+        ["osrs", command, ...args],
+        (output) => shell.print(output),
+        width
+      );
+    } catch (error) {
+      shell.print(`${error.message}\n`);
+    }
   });
 shell.repl();
 